@@ -0,0 +1,184 @@
+//! Sound effects.
+//!
+//! Which sounds play for what is read from a data file, so sounds can be swapped or added
+//! without touching code. Anything holding the [`Game`](crate::Game) plugin can ask for a
+//! [`Cue`], the plugin also plays the cues that follow from game events on its own. Sounds
+//! play in the current scene and are freed once they finish.
+use crate::{chart_gen::ChartRng, events::GameEvent, judge::Judgement, settings::Settings};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::log::Log,
+    scene::{
+        base::BaseBuilder,
+        sound::{SoundBuffer, SoundBuilder, Status},
+        Scene,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Where the sounds of every cue are listed, relative to the working directory.
+pub const SOUND_MAP_PATH: &str = "data/sounds.ron";
+
+/// Something that makes a sound.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cue {
+    Footstep,
+    /// The line goes out as a catch starts.
+    Cast,
+    /// A lane key goes down during a catch.
+    ReelClick,
+    Great,
+    Good,
+    Ok,
+    Miss,
+    CatchWon,
+    CatchLost,
+    /// A menu button was clicked.
+    UiClick,
+    /// A menu was backed out of.
+    UiBack,
+}
+
+impl Cue {
+    pub fn for_judgement(judgement: Judgement) -> Self {
+        match judgement {
+            Judgement::Great => Cue::Great,
+            Judgement::Good => Cue::Good,
+            Judgement::Ok => Cue::Ok,
+            Judgement::Miss => Cue::Miss,
+        }
+    }
+
+    pub fn category(self) -> SoundCategory {
+        match self {
+            Cue::UiClick | Cue::UiBack => SoundCategory::Interface,
+            _ => SoundCategory::Effects,
+        }
+    }
+}
+
+/// Groups of sounds that each have their own volume setting.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCategory {
+    Effects,
+    Interface,
+    Music,
+}
+
+impl SoundCategory {
+    /// Gain the settings give this category, master volume included.
+    pub fn gain(self, settings: &Settings) -> f32 {
+        match self {
+            SoundCategory::Effects => settings.sfx_gain(),
+            SoundCategory::Interface => settings.ui_gain(),
+            SoundCategory::Music => settings.music_gain(),
+        }
+    }
+}
+
+fn default_gain() -> f32 {
+    1.0
+}
+
+/// Sounds of one cue. One of them is picked every time the cue plays, so repeated cues such
+/// as footsteps do not all sound the same.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CueSounds {
+    pub sounds: Vec<String>,
+    /// Multiplied with the volume of the cue's category.
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SoundMap {
+    /// Cues missing from the map stay silent.
+    pub cues: HashMap<Cue, CueSounds>,
+}
+
+impl SoundMap {
+    /// Reads the sound map, falling back to silence if it is missing or broken.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|e| e.to_string()));
+        match result {
+            Ok(map) => map,
+            Err(e) => {
+                Log::err(format!("Unable to load sound map {}: {e}", path.display()));
+                Self::default()
+            }
+        }
+    }
+}
+
+pub struct AudioManager {
+    map: SoundMap,
+    //cues asked for since the last update
+    queued: Vec<Cue>,
+    //picks between the sounds of a cue
+    rng: ChartRng,
+}
+
+impl AudioManager {
+    pub fn new(map: SoundMap) -> Self {
+        Self {
+            map,
+            queued: Vec::new(),
+            rng: ChartRng::new(0),
+        }
+    }
+
+    /// Plays a cue on the next update.
+    pub fn play(&mut self, cue: Cue) {
+        self.queued.push(cue);
+    }
+
+    /// Plays the cues of this frame's events and everything asked for since the last update.
+    /// Without a scene to play in, the cues are dropped.
+    pub fn update(
+        &mut self,
+        events: &[GameEvent],
+        scene: Option<&mut Scene>,
+        settings: &Settings,
+        resource_manager: &ResourceManager,
+    ) {
+        for event in events {
+            match event {
+                GameEvent::Judged { judgement, .. } => {
+                    self.queued.push(Cue::for_judgement(*judgement))
+                }
+                GameEvent::CatchStarted { .. } => self.queued.push(Cue::Cast),
+                GameEvent::CatchEnded { won: true, .. } => self.queued.push(Cue::CatchWon),
+                GameEvent::CatchEnded { won: false, .. } => self.queued.push(Cue::CatchLost),
+                _ => (),
+            }
+        }
+
+        let Some(scene) = scene else {
+            self.queued.clear();
+            return;
+        };
+        //a chord is judged all at once, it only needs to be heard once
+        self.queued.dedup();
+        for cue in std::mem::take(&mut self.queued) {
+            let Some(sounds) = self.map.cues.get(&cue) else {
+                continue;
+            };
+            if sounds.sounds.is_empty() {
+                continue;
+            }
+            let path = &sounds.sounds[self.rng.below(sounds.sounds.len())];
+            let buffer = resource_manager.request::<SoundBuffer>(path);
+            SoundBuilder::new(BaseBuilder::new())
+                .with_buffer(Some(buffer))
+                .with_status(Status::Playing)
+                .with_play_once(true)
+                .with_spatial_blend_factor(0.0)
+                .with_gain(sounds.gain * cue.category().gain(settings))
+                .build(&mut scene.graph);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Plays a chart by itself, for testing charts and for demos.
+//!
+//! Autoplay presses lanes exactly like the keyboard does, through [`NoteJudge::press`], just
+//! at the ideal moment of every note. A chart that does not come out all Greats under
+//! autoplay has notes the judge cannot tell apart.
+//!
+//! [`NoteJudge::press`]: crate::judge::NoteJudge::press
+use crate::chart::{Chart, ChartNote};
+
+#[derive(Debug, Default, Clone)]
+pub struct Autoplay {
+    //index of the first chart note that has not been pressed yet
+    next: usize,
+}
+
+impl Autoplay {
+    /// Skips every note before `time`, used when starting part way into a chart.
+    pub fn seek(&mut self, chart: &Chart, time: f32) {
+        self.next = chart.notes.partition_point(|note| note.time < time);
+    }
+
+    /// Notes to press now that the song has reached `time`.
+    pub fn due<'a>(&mut self, chart: &'a Chart, time: f32) -> &'a [ChartNote] {
+        let start = self.next.min(chart.notes.len());
+        let end = start + chart.notes[start..].partition_point(|note| note.time <= time);
+        self.next = end;
+        &chart.notes[start..end]
+    }
+}
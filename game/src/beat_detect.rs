@@ -0,0 +1,442 @@
+//! Offline beat detection, used to get a first-pass chart out of a music track.
+//!
+//! The track is decoded to mono, an onset envelope is built from its spectral flux, the tempo
+//! is estimated by autocorrelating that envelope and the strongest onsets are snapped to the
+//! resulting beat grid. The output is a regular [`Chart`], ready to be tweaked by hand.
+use crate::{
+    chart::{Chart, ChartNote},
+    lanes::LaneLayout,
+};
+use std::{
+    f32::consts::PI,
+    fmt::{Display, Formatter},
+    fs::File,
+    path::Path,
+};
+
+/// Samples per analysis frame, must be a power of two.
+const FRAME_SIZE: usize = 1024;
+/// Samples between the starts of two frames.
+const HOP_SIZE: usize = 512;
+/// Tempo range the detector searches, in beats per minute.
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+/// Tempo most songs sit around, used to settle on one of two tempos an octave apart.
+const PREFERRED_BPM: f32 = 120.0;
+
+/// Decoded audio, mixed down to one channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audio {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+/// All possible errors that may occur while reading a track.
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    Wav(hound::Error),
+    Ogg(lewton::VorbisError),
+    /// The file extension is neither `wav` nor `ogg`.
+    UnsupportedFormat(String),
+    /// The track decoded fine but has no samples.
+    Empty,
+}
+
+impl Display for AudioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioError::Io(e) => write!(f, "Audio io error: {e}"),
+            AudioError::Wav(e) => write!(f, "Unable to decode wav: {e}"),
+            AudioError::Ogg(e) => write!(f, "Unable to decode ogg: {e}"),
+            AudioError::UnsupportedFormat(ext) => {
+                write!(f, "Unsupported audio format {ext:?}, expected wav or ogg.")
+            }
+            AudioError::Empty => write!(f, "Audio file has no samples."),
+        }
+    }
+}
+
+impl From<std::io::Error> for AudioError {
+    fn from(e: std::io::Error) -> Self {
+        AudioError::Io(e)
+    }
+}
+
+impl From<hound::Error> for AudioError {
+    fn from(e: hound::Error) -> Self {
+        AudioError::Wav(e)
+    }
+}
+
+impl From<lewton::VorbisError> for AudioError {
+    fn from(e: lewton::VorbisError) -> Self {
+        AudioError::Ogg(e)
+    }
+}
+
+impl Audio {
+    /// Decodes a WAV or OGG file, picked by extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AudioError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let audio = match extension.as_str() {
+            "wav" => Self::load_wav(path)?,
+            "ogg" => Self::load_ogg(path)?,
+            _ => return Err(AudioError::UnsupportedFormat(extension)),
+        };
+        if audio.samples.is_empty() {
+            return Err(AudioError::Empty);
+        }
+        Ok(audio)
+    }
+
+    fn load_wav(path: &Path) -> Result<Self, AudioError> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let interleaved = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|s| s as f32 / scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        Ok(Self {
+            sample_rate: spec.sample_rate,
+            samples: mix_down(&interleaved, spec.channels as usize),
+        })
+    }
+
+    fn load_ogg(path: &Path) -> Result<Self, AudioError> {
+        let mut reader = lewton::inside_ogg::OggStreamReader::new(File::open(path)?)?;
+        let channels = reader.ident_hdr.audio_channels as usize;
+        let mut interleaved = Vec::new();
+        while let Some(packet) = reader.read_dec_packet_itl()? {
+            interleaved.extend(packet.into_iter().map(|s| s as f32 / 32768.0));
+        }
+        Ok(Self {
+            sample_rate: reader.ident_hdr.audio_sample_rate,
+            samples: mix_down(&interleaved, channels),
+        })
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+}
+
+fn mix_down(interleaved: &[f32], channels: usize) -> Vec<f32> {
+    let channels = channels.max(1);
+    interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// One detected onset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Onset {
+    /// Seconds from the start of the track.
+    pub time: f32,
+    /// Spectral flux at the onset, only meaningful compared to other onsets of the same track.
+    pub strength: f32,
+    /// Where in the spectrum the onset happened, 0.0 for bass and 1.0 for the highest band.
+    pub pitch: f32,
+}
+
+/// Everything the detector found out about a track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub bpm: f32,
+    /// Seconds from the start of the track to the first beat.
+    pub first_beat: f32,
+    pub duration: f32,
+    pub onsets: Vec<Onset>,
+}
+
+/// Runs the full analysis on a decoded track.
+pub fn analyse(audio: &Audio) -> Analysis {
+    let frame_rate = audio.sample_rate as f32 / HOP_SIZE as f32;
+    //frames are timed by their centre
+    let latency = FRAME_SIZE as f32 / 2.0 / audio.sample_rate as f32;
+    let (envelope, pitches) = spectral_flux(&audio.samples);
+    let (bpm, first_beat) = estimate_tempo(&envelope, frame_rate);
+    let onsets: Vec<Onset> = pick_peaks(&envelope)
+        .into_iter()
+        .map(|frame| Onset {
+            time: frame as f32 / frame_rate + latency,
+            strength: envelope[frame],
+            pitch: pitches[frame],
+        })
+        .collect();
+
+    //the tempo and phase searches only have frame resolution. fitting a line through the
+    //onsets that land close to a beat pins both down and stops the grid drifting over a
+    //long track
+    let mut beat = 60.0 / bpm;
+    let mut first_beat = first_beat + latency;
+    for _ in 0..2 {
+        let points: Vec<(f32, f32)> = onsets
+            .iter()
+            .filter_map(|onset| {
+                let position = (onset.time - first_beat) / beat;
+                let error = (position - position.round()) * beat;
+                (error.abs() < beat / 8.0).then_some((position.round(), onset.time))
+            })
+            .collect();
+        if points.len() < 2 {
+            break;
+        }
+        let n = points.len() as f32;
+        let mean_k = points.iter().map(|(k, _)| k).sum::<f32>() / n;
+        let mean_t = points.iter().map(|(_, t)| t).sum::<f32>() / n;
+        let covariance: f32 = points.iter().map(|(k, t)| (k - mean_k) * (t - mean_t)).sum();
+        let variance: f32 = points.iter().map(|(k, _)| (k - mean_k).powi(2)).sum();
+        if variance <= f32::EPSILON {
+            break;
+        }
+        beat = covariance / variance;
+        first_beat = mean_t - beat * mean_k;
+    }
+
+    Analysis {
+        bpm: 60.0 / beat,
+        first_beat: first_beat.rem_euclid(beat),
+        duration: audio.duration(),
+        onsets,
+    }
+}
+
+//onset strength and spectral centroid of the increase for every frame
+fn spectral_flux(samples: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+    let bins = FRAME_SIZE / 2;
+    let mut previous = vec![0.0; bins];
+    let mut envelope = Vec::new();
+    let mut pitches = Vec::new();
+    let mut buffer = vec![(0.0, 0.0); FRAME_SIZE];
+
+    let mut start = 0;
+    while start + FRAME_SIZE <= samples.len() {
+        for (i, value) in buffer.iter_mut().enumerate() {
+            *value = (samples[start + i] * window[i], 0.0);
+        }
+        fft(&mut buffer);
+
+        let mut flux = 0.0;
+        let mut weighted = 0.0;
+        for (bin, (re, im)) in buffer[..bins].iter().enumerate() {
+            //log compression keeps loud passages from drowning out quiet ones
+            let magnitude = (1.0 + 100.0 * (re * re + im * im).sqrt()).ln();
+            let rise = (magnitude - previous[bin]).max(0.0);
+            flux += rise;
+            weighted += rise * bin as f32;
+            previous[bin] = magnitude;
+        }
+        envelope.push(flux);
+        //square root spreads the low end, where most of the musical content is
+        pitches.push(if flux > 0.0 {
+            (weighted / flux / bins as f32).sqrt().min(1.0)
+        } else {
+            0.0
+        });
+        start += HOP_SIZE;
+    }
+
+    //the first frame rises from silence and would always count as an onset
+    if let Some(first) = envelope.first_mut() {
+        *first = 0.0;
+    }
+    (envelope, pitches)
+}
+
+//in-place iterative radix-2 fft over (re, im) pairs
+fn fft(buffer: &mut [(f32, f32)]) {
+    let n = buffer.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let angle = -2.0 * PI / length as f32;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (re, im) = buffer[start + k + length / 2];
+                let twiddled = (re * cos - im * sin, re * sin + im * cos);
+                let even = buffer[start + k];
+                buffer[start + k] = (even.0 + twiddled.0, even.1 + twiddled.1);
+                buffer[start + k + length / 2] = (even.0 - twiddled.0, even.1 - twiddled.1);
+            }
+        }
+        length <<= 1;
+    }
+}
+
+//tempo from the autocorrelation of the envelope, then the phase that lines the most onset
+//energy up with the beat
+fn estimate_tempo(envelope: &[f32], frame_rate: f32) -> (f32, f32) {
+    let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+    let centred: Vec<f32> = envelope.iter().map(|v| v - mean).collect();
+
+    let min_lag = (frame_rate * 60.0 / MAX_BPM).floor().max(1.0) as usize;
+    let max_lag = (frame_rate * 60.0 / MIN_BPM).ceil() as usize;
+    if centred.len() <= max_lag + 1 {
+        return (PREFERRED_BPM, 0.0);
+    }
+
+    let correlation = |lag: usize| -> f32 {
+        centred
+            .iter()
+            .zip(&centred[lag..])
+            .map(|(a, b)| a * b)
+            .sum::<f32>()
+            / (centred.len() - lag) as f32
+    };
+    let scores: Vec<f32> = (min_lag..=max_lag)
+        .map(|lag| {
+            let bpm = 60.0 * frame_rate / lag as f32;
+            //a gentle preference for tempos near the usual one settles octave ambiguity
+            let octaves = (bpm / PREFERRED_BPM).log2();
+            correlation(lag) * (-0.5 * octaves * octaves).exp()
+        })
+        .collect();
+    let best = scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(i, _)| i)
+        .unwrap_or_default();
+
+    //parabolic interpolation around the best lag gives a tempo between whole frames
+    let mut lag = (min_lag + best) as f32;
+    if best > 0 && best + 1 < scores.len() {
+        let (a, b, c) = (scores[best - 1], scores[best], scores[best + 1]);
+        let denominator = a - 2.0 * b + c;
+        if denominator.abs() > f32::EPSILON {
+            lag += (0.5 * (a - c) / denominator).clamp(-0.5, 0.5);
+        }
+    }
+
+    //alternating kick and snare patterns correlate best at two beats, halve the period as
+    //long as the shorter one still correlates well
+    while lag / 2.0 >= min_lag as f32
+        && correlation((lag / 2.0).round() as usize) >= 0.5 * correlation(lag.round() as usize)
+    {
+        lag /= 2.0;
+    }
+    let bpm = 60.0 * frame_rate / lag;
+
+    let period = lag;
+    let phase = (0..lag.ceil() as usize)
+        .max_by(|a, b| {
+            let energy = |phase: usize| -> f32 {
+                let mut total = 0.0;
+                let mut position = phase as f32;
+                while (position as usize) < envelope.len() {
+                    total += envelope[position.round() as usize % envelope.len()];
+                    position += period;
+                }
+                total
+            };
+            energy(*a).total_cmp(&energy(*b))
+        })
+        .unwrap_or_default();
+
+    (bpm, phase as f32 / frame_rate)
+}
+
+//frames that are a local maximum and stand out from their surroundings
+fn pick_peaks(envelope: &[f32]) -> Vec<usize> {
+    const MAX_RADIUS: usize = 3;
+    const MEAN_RADIUS: usize = 8;
+    const DELTA: f32 = 0.1;
+
+    let global_mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+    let mut peaks = Vec::new();
+    for (i, value) in envelope.iter().enumerate() {
+        let around = |radius: usize| {
+            &envelope[i.saturating_sub(radius)..(i + radius + 1).min(envelope.len())]
+        };
+        let is_max = around(MAX_RADIUS).iter().all(|v| v <= value);
+        let local = around(MEAN_RADIUS);
+        let local_mean = local.iter().sum::<f32>() / local.len() as f32;
+        if is_max && *value > local_mean + DELTA * global_mean && *value > global_mean {
+            //plateaus only count once
+            if peaks.last().is_none_or(|last| i - last > MAX_RADIUS) {
+                peaks.push(i);
+            }
+        }
+    }
+    peaks
+}
+
+/// Snaps the onsets of an analysis to its beat grid and turns them into a chart.
+///
+/// `density` works like the generator's: 1.0 keeps roughly the strongest onset per beat
+/// on an eighth-note grid, lower values keep fewer and snap to whole beats. Lanes follow
+/// pitch, low sounds on the left and high sounds on the right, spread evenly over the lanes
+/// whatever the range of the track.
+pub fn to_chart(analysis: &Analysis, layout: LaneLayout, density: f32) -> Chart {
+    let beat = 60.0 / analysis.bpm;
+    let grid = if density >= 1.0 { beat / 2.0 } else { beat };
+    let lane_count = layout.lane_count();
+
+    //keeps the strongest onsets, as many as the density asks for
+    let wanted = ((analysis.duration / beat) * density.max(0.0)).round() as usize;
+    let mut onsets = analysis.onsets.clone();
+    onsets.sort_by(|a, b| b.strength.total_cmp(&a.strength));
+    onsets.truncate(wanted);
+
+    let mut pitches: Vec<f32> = onsets.iter().map(|onset| onset.pitch).collect();
+    pitches.sort_by(f32::total_cmp);
+    //share of the kept onsets that are lower than the given pitch
+    let pitch_rank = |pitch: f32| {
+        pitches.partition_point(|p| *p < pitch) as f32 / pitches.len().max(1) as f32
+    };
+
+    let mut taken = std::collections::HashSet::new();
+    let mut notes = Vec::new();
+    for onset in onsets {
+        let slot = ((onset.time - analysis.first_beat) / grid).round() as i64;
+        let time = analysis.first_beat + slot as f32 * grid;
+        if time < 0.0 || !taken.insert(slot) {
+            continue;
+        }
+        let index = ((pitch_rank(onset.pitch) * lane_count as f32) as usize).min(lane_count - 1);
+        notes.push(ChartNote {
+            time,
+            lane: layout.lanes()[index],
+            hold: 0.0,
+        });
+    }
+
+    let mut chart = Chart {
+        bpm: analysis.bpm,
+        offset: analysis.first_beat,
+        layout,
+        song: None,
+        notes,
+    };
+    chart.sort();
+    chart
+}
@@ -0,0 +1,70 @@
+//! Turns a music track into a first-pass chart.
+//!
+//! ```text
+//! beat_detect <track.ogg|track.wav> <chart.ron> [--layout four|five|six] [--density 1.0]
+//! ```
+use fishing_hero::{beat_detect, lanes::LaneLayout};
+use std::process::ExitCode;
+
+const USAGE: &str =
+    "usage: beat_detect <track.ogg|track.wav> <chart.ron> [--layout four|five|six] [--density 1.0]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut paths = Vec::new();
+    let mut layout = LaneLayout::default();
+    let mut density = 1.0;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--layout" => {
+                layout = match args.next().map(|s| s.as_str()) {
+                    Some("four") => LaneLayout::Four,
+                    Some("five") => LaneLayout::Five,
+                    Some("six") => LaneLayout::Six,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            "--density" => match args.next().and_then(|s| s.parse().ok()) {
+                Some(value) => density = value,
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ => paths.push(arg.clone()),
+        }
+    }
+    let [input, output] = paths.as_slice() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    let audio = match beat_detect::Audio::load(input) {
+        Ok(audio) => audio,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let analysis = beat_detect::analyse(&audio);
+    let mut chart = beat_detect::to_chart(&analysis, layout, density);
+    chart.song = Some(input.clone());
+    if let Err(e) = chart.save(output) {
+        eprintln!("{e}");
+        return ExitCode::FAILURE;
+    }
+
+    println!(
+        "{:.1} bpm, first beat at {:.3}s, {} onsets, {} notes written to {output}",
+        analysis.bpm,
+        analysis.first_beat,
+        analysis.onsets.len(),
+        chart.notes.len()
+    );
+    ExitCode::SUCCESS
+}
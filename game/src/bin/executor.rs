@@ -0,0 +1,18 @@
+//! Runs the game in a window.
+use fishing_hero::{
+    settings::{Settings, SETTINGS_PATH},
+    GameConstructor,
+};
+use fyrox::{engine::executor::Executor, event_loop::EventLoop};
+
+fn main() {
+    //vsync can only be chosen when the graphics context is created, so the settings are read
+    //here as well as by the game
+    let settings = Settings::load(SETTINGS_PATH);
+    let mut executor = Executor::from_params(
+        EventLoop::new().unwrap(),
+        settings.graphics_context_params("Fishing Hero"),
+    );
+    executor.add_plugin_constructor(GameConstructor);
+    executor.run()
+}
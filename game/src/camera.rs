@@ -1,15 +1,18 @@
 //! Contains all methods and structures to create and manage cameras. See [`Camera`] docs for more info.
-
-
-use crate::camera::lazy_static::lazy_static;
+//!
+//! This is the game's own copy of the engine's camera node. The renderer only draws through
+//! [`fyrox::scene::camera::Camera`], which it looks up by its concrete type, so every
+//! [`Camera`] renders through an engine camera of its own: [`sync_render_cameras`] adds one
+//! as its child and copies the projection, viewport and the rest of the settings over every
+//! frame. The child inherits the transform, so [`FollowCamera`] and anything else moving the
+//! camera moves the picture too.
+//!
+//! [`FollowCamera`]: crate::follow_camera::FollowCamera
 
 use fyrox::{
-    resource::texture::{
-        CompressionOptions, TextureImportOptions, TextureMinificationFilter,
-    },
     asset::{ResourceLoadError, ResourceStateRef},
     core::{
-        algebra::{Vector2, Vector3, Matrix4, Point3, Vector4},
+        algebra::{Matrix4, Point3, Vector2, Vector3, Vector4},
         color::Color,
         log::Log,
         math::{aabb::AxisAlignedBoundingBox, frustum::Frustum, ray::Ray, Rect},
@@ -27,20 +30,16 @@ use fyrox::{
         base::{Base, BaseBuilder},
         debug::SceneDrawingContext,
         graph::Graph,
-        node::{Node, NodeTrait, UpdateContext}, Scene,
-    }, 
-    lazy_static,
-    script::{ScriptContext, ScriptTrait},
-
+        camera as engine,
+        node::{Node, NodeTrait, UpdateContext},
+    },
 };
 use std::{
     fmt::{Display, Formatter},
     ops::{Deref, DerefMut},
-    sync::Arc,
+    sync::{Arc, OnceLock},
 };
 
-
-
 /// Perspective projection make parallel lines to converge at some point. Objects will be smaller
 /// with increasing distance. This the projection type "used" by human eyes, photographic lens and
 /// it looks most realistic.
@@ -286,8 +285,8 @@ impl Default for Exposure {
    }
 }
 
-/// Camera allows you to see world from specific point in world. You must have at least one camera in
-/// your scene to see anything.
+/// Camera describes how the world is seen from a specific point in it. The scene is drawn
+/// through an engine camera kept in sync with it, see the [module docs](self).
 ///
 /// ## Projection
 ///
@@ -299,16 +298,6 @@ impl Default for Exposure {
 ///
 /// Skybox is a cube around the camera with six textures forming seamless "sky". It could be anything,
 /// starting from simple blue sky and ending with outer space.
-///
-/// ## Multiple cameras
-///
-/// Fyrox supports multiple cameras per scene, it means that you can create split screen games, make
-/// picture-in-picture insertions in your main camera view and any other combinations you need.
-///
-/// ## Performance
-///
-/// Each camera forces engine to re-render same scene one more time, which may cause almost double load
-/// of your GPU.
 #[derive(Debug, Visit, Reflect, Clone)]
 pub struct Camera {
    base: Base,
@@ -322,8 +311,8 @@ pub struct Camera {
    #[reflect(setter = "set_enabled")]
    enabled: InheritableVariable<bool>,
 
-//    #[reflect(setter = "set_skybox")]
-//    sky_box: InheritableVariable<Option<SkyBox>>,
+   #[reflect(setter = "set_skybox")]
+   sky_box: InheritableVariable<Option<SkyBox>>,
 
    #[reflect(setter = "set_environment")]
    environment: InheritableVariable<Option<TextureResource>>,
@@ -344,7 +333,10 @@ pub struct Camera {
    #[visit(skip)]
    #[reflect(hidden)]
    projection_matrix: Matrix4<f32>,
-sky_box: InheritableVariable<Option<SkyBox>>,
+
+   #[visit(skip)]
+   #[reflect(hidden)]
+   render_camera: Handle<Node>,
 }
 
 impl Deref for Camera {
@@ -369,7 +361,7 @@ impl Default for Camera {
 
 impl TypeUuidProvider for Camera {
    fn type_uuid() -> Uuid {
-       uuid!("198d3aca-433c-4ce1-bb25-3190699b757f")
+       uuid!("b3e5d0c2-6f1a-4c8e-a7d4-2e9f5b1c8a63")
    }
 }
 
@@ -690,10 +682,117 @@ impl Camera {
    pub fn exposure(&self) -> Exposure {
        *self.exposure
    }
+
+   /// Engine camera the scene is drawn through, `Handle::NONE` until
+   /// [`sync_render_cameras`] added it.
+   pub fn render_camera(&self) -> Handle<Node> {
+       self.render_camera
+   }
+}
+
+/// Name of the engine camera added under every [`Camera`].
+pub const RENDER_CAMERA_NAME: &str = "Render Camera";
+
+/// Gives every [`Camera`] of the graph an engine camera child to be drawn through and copies
+/// its settings over. Called once a frame, the sky box and colour grading table are only
+/// rebuilt when they change.
+pub fn sync_render_cameras(graph: &mut Graph) {
+   let cameras: Vec<_> = graph
+       .pair_iter()
+       .filter(|(_, node)| node.cast::<Camera>().is_some())
+       .map(|(handle, _)| handle)
+       .collect();
+   for handle in cameras {
+       let mut render_camera = graph[handle].cast::<Camera>().unwrap().render_camera;
+       if graph
+           .try_get(render_camera)
+           .and_then(|node| node.cast::<engine::Camera>())
+           .is_none()
+       {
+           render_camera =
+               engine::CameraBuilder::new(BaseBuilder::new().with_name(RENDER_CAMERA_NAME))
+                   .build(graph);
+           graph.link_nodes(render_camera, handle);
+           graph[handle].cast_mut::<Camera>().unwrap().render_camera = render_camera;
+       }
+
+       let camera = graph[handle].cast::<Camera>().unwrap();
+       let projection = match camera.projection() {
+           Projection::Perspective(p) => {
+               engine::Projection::Perspective(engine::PerspectiveProjection {
+                   fov: p.fov,
+                   z_near: p.z_near,
+                   z_far: p.z_far,
+               })
+           }
+           Projection::Orthographic(p) => {
+               engine::Projection::Orthographic(engine::OrthographicProjection {
+                   z_near: p.z_near,
+                   z_far: p.z_far,
+                   vertical_size: p.vertical_size,
+               })
+           }
+       };
+       let exposure = match camera.exposure() {
+           Exposure::Auto {
+               key_value,
+               min_luminance,
+               max_luminance,
+           } => engine::Exposure::Auto {
+               key_value,
+               min_luminance,
+               max_luminance,
+           },
+           Exposure::Manual(exposure) => engine::Exposure::Manual(exposure),
+       };
+       let viewport = camera.viewport();
+       let enabled = camera.is_enabled();
+       let environment = camera.environment_map();
+       let color_grading_enabled = camera.color_grading_enabled();
+       let skybox = camera.skybox_ref().map(SkyBox::textures);
+       let lut = camera
+           .color_grading_lut_ref()
+           .and_then(|lut| lut.unwrapped_lut.clone());
+
+       let render = graph[render_camera].cast_mut::<engine::Camera>().unwrap();
+       render.set_projection(projection);
+       render.set_exposure(exposure);
+       render.set_viewport(viewport);
+       render.set_enabled(enabled);
+       render.set_environment(environment);
+       render.set_color_grading_enabled(color_grading_enabled);
+       if render.skybox_ref().map(engine::SkyBox::textures) != skybox {
+           let skybox = skybox.and_then(|[left, right, top, bottom, front, back]| {
+               engine::SkyBoxBuilder {
+                   front,
+                   back,
+                   left,
+                   right,
+                   top,
+                   bottom,
+               }
+               .build()
+               .map_err(|e| Log::err(format!("Unable to build the sky box: {e:?}")))
+               .ok()
+           });
+           render.set_skybox(skybox);
+       }
+       if render.color_grading_lut_ref().map(engine::ColorGradingLut::unwrapped_lut) != lut {
+           //our own table was built from the same texture, so it is loaded by now
+           let lut = lut.and_then(|lut| {
+               fyrox::core::futures::executor::block_on(engine::ColorGradingLut::new(lut))
+                   .map_err(|e| {
+                       Log::err(format!("Unable to build the colour grading table: {e:?}"))
+                   })
+                   .ok()
+           });
+           render.set_color_grading_lut(lut);
+       }
+   }
 }
 
 impl NodeTrait for Camera {
-   //crate::impl_query_component!();
+   fyrox::impl_query_component!();
 
    /// Returns current **local-space** bounding box.
    #[inline]
@@ -718,41 +817,6 @@ impl NodeTrait for Camera {
    fn debug_draw(&self, ctx: &mut SceneDrawingContext) {
        ctx.draw_frustum(&self.frustum(), Color::ORANGE);
    }
-
-fn on_removed_from_graph(&mut self, #[allow(unused_variables)] graph: &mut Graph) {}
-
-fn sync_native(
-        &self,
-        #[allow(unused_variables)] self_handle: Handle<Node>,
-        #[allow(unused_variables)] context: &mut fyrox::scene::node::SyncContext,
-    ) {
-    }
-
-fn sync_transform(
-        &self,
-        #[allow(unused_variables)] new_global_transform: &Matrix4<f32>,
-        _context: &mut fyrox::scene::node::SyncContext,
-    ) {
-    }
-
-fn is_alive(&self) -> bool {
-        true
-    }
-
-fn collect_render_data(&self, #[allow(unused_variables)] ctx: &mut fyrox::renderer::batch::RenderContext) {}
-
-fn validate(&self, #[allow(unused_variables)] scene: &fyrox::scene::Scene) -> Result<(), String> {
-        Ok(())
-    }
-
-fn query_component_ref(&self, type_id: std::any::TypeId) -> Option<&dyn std::any::Any> {
-        todo!()
-    }
-
-fn query_component_mut(&mut self, type_id: std::any::TypeId) -> Option<&mut dyn std::any::Any> {
-        todo!()
-    }
-
 }
 
 /// All possible error that may occur during color grading look-up table creation.
@@ -826,7 +890,7 @@ impl ColorGradingLut {
    /// Typical usage would be:
    ///
    /// ```no_run
-   /// use fyrox::scene::camera::ColorGradingLut;
+   /// use fishing_hero::camera::ColorGradingLut;
    /// use fyrox::asset::manager::{ResourceManager};
    /// use fyrox::resource::texture::Texture;
    ///
@@ -952,57 +1016,21 @@ pub enum SkyBoxKind {
    Specific(SkyBox),
 }
 
-fn load_texture(data: &[u8], id: &str) -> TextureResource {
-   let texture = TextureResource::load_from_memory(
-       data,
-       TextureImportOptions::default()
-           .with_compression(CompressionOptions::NoCompression)
-           .with_minification_filter(TextureMinificationFilter::Linear),
-   )
-   .ok()
-   .unwrap();
-   texture.data_ref().set_path(id);
-   texture
-}
-
-lazy_static! {
-   static ref BUILT_IN_SKYBOX_FRONT: TextureResource = load_texture(
-       include_bytes!("data/16x16 Outdoors Tileset/Outdoors_04.png"),
-       "__BUILT_IN_SKYBOX_FRONT",
-   );
-   static ref BUILT_IN_SKYBOX_BACK: TextureResource =
-       load_texture(include_bytes!("data/16x16 Outdoors Tileset/Outdoors_04.png"), "__BUILT_IN_SKYBOX_BACK",);
-   static ref BUILT_IN_SKYBOX_TOP: TextureResource =
-       load_texture(include_bytes!("data/16x16 Outdoors Tileset/Outdoors_04.png"), "__BUILT_IN_SKYBOX_TOP",);
-   static ref BUILT_IN_SKYBOX_BOTTOM: TextureResource = load_texture(
-       include_bytes!("data/16x16 Outdoors Tileset/Outdoors_04.png"),
-       "__BUILT_IN_SKYBOX_BOTTOM",
-   );
-   static ref BUILT_IN_SKYBOX_LEFT: TextureResource =
-       load_texture(include_bytes!("data/16x16 Outdoors Tileset/Outdoors_04.png"), "__BUILT_IN_SKYBOX_LEFT",);
-   static ref BUILT_IN_SKYBOX_RIGHT: TextureResource = load_texture(
-       include_bytes!("data/16x16 Outdoors Tileset/Outdoors_04.png"),
-       "__BUILT_IN_SKYBOX_RIGHT",
-   );
-   static ref BUILT_IN_SKYBOX: SkyBox = SkyBoxKind::make_built_in_skybox();
-}
+//built once on first use, from the engine's own sky textures
+static BUILT_IN_SKYBOX: OnceLock<SkyBox> = OnceLock::new();
 
 impl SkyBoxKind {
    fn make_built_in_skybox() -> SkyBox {
-       let front = BUILT_IN_SKYBOX_FRONT.clone();
-       let back = BUILT_IN_SKYBOX_BACK.clone();
-       let top = BUILT_IN_SKYBOX_TOP.clone();
-       let bottom = BUILT_IN_SKYBOX_BOTTOM.clone();
-       let left = BUILT_IN_SKYBOX_LEFT.clone();
-       let right = BUILT_IN_SKYBOX_RIGHT.clone();
+       let [front, back, top, bottom, left, right] =
+           SkyBoxKind::built_in_skybox_textures().map(|texture| Some(texture.clone()));
 
        SkyBoxBuilder {
-           front: Some(front),
-           back: Some(back),
-           left: Some(left),
-           right: Some(right),
-           top: Some(top),
-           bottom: Some(bottom),
+           front,
+           back,
+           left,
+           right,
+           top,
+           bottom,
        }
        .build()
        .unwrap()
@@ -1010,20 +1038,13 @@ impl SkyBoxKind {
 
    /// Returns a references to built-in sky box.
    pub fn built_in_skybox() -> &'static SkyBox {
-       &BUILT_IN_SKYBOX
+       BUILT_IN_SKYBOX.get_or_init(SkyBoxKind::make_built_in_skybox)
    }
 
    /// Returns an array with references to the textures being used in built-in sky box. The order is:
-   /// front, back, top, bottom, left, right.
+   /// front, back, top, bottom, left, right. These are the textures of the engine's built-in sky box.
    pub fn built_in_skybox_textures() -> [&'static TextureResource; 6] {
-       [
-           &BUILT_IN_SKYBOX_FRONT,
-           &BUILT_IN_SKYBOX_BACK,
-           &BUILT_IN_SKYBOX_TOP,
-           &BUILT_IN_SKYBOX_BOTTOM,
-           &BUILT_IN_SKYBOX_LEFT,
-           &BUILT_IN_SKYBOX_RIGHT,
-       ]
+       engine::SkyBoxKind::built_in_skybox_textures()
    }
 }
 
@@ -1031,9 +1052,6 @@ impl SkyBoxKind {
 /// This is typical implementation of Builder pattern.
 pub struct CameraBuilder {
    base_builder: BaseBuilder,
-   fov: f32,
-   z_near: f32,
-   z_far: f32,
    viewport: Rect<f32>,
    enabled: bool,
    skybox: SkyBoxKind,
@@ -1050,9 +1068,6 @@ impl CameraBuilder {
        Self {
            enabled: true,
            base_builder,
-           fov: 75.0f32.to_radians(),
-           z_near: 0.025,
-           z_far: 2048.0,
            viewport: Rect::new(0.0, 0.0, 1.0, 1.0),
            skybox: SkyBoxKind::Builtin,
            environment: None,
@@ -1063,21 +1078,23 @@ impl CameraBuilder {
        }
    }
 
-   /// Sets desired field of view in radians.
+   /// Sets desired field of view in radians, only used by perspective projection.
    pub fn with_fov(mut self, fov: f32) -> Self {
-       self.fov = fov;
+       if let Projection::Perspective(perspective) = &mut self.projection {
+           perspective.fov = fov;
+       }
        self
    }
 
    /// Sets desired near projection plane.
    pub fn with_z_near(mut self, z_near: f32) -> Self {
-       self.z_near = z_near;
+       self.projection.set_z_near(z_near);
        self
    }
 
    /// Sets desired far projection plane.
    pub fn with_z_far(mut self, z_far: f32) -> Self {
-       self.z_far = z_far;
+       self.projection.set_z_far(z_far);
        self
    }
 
@@ -1155,6 +1172,7 @@ impl CameraBuilder {
            exposure: self.exposure.into(),
            color_grading_lut: self.color_grading_lut.into(),
            color_grading_enabled: self.color_grading_enabled.into(),
+           render_camera: Handle::NONE,
        }
    }
 
@@ -1167,19 +1185,6 @@ impl CameraBuilder {
    pub fn build(self, graph: &mut Graph) -> Handle<Node> {
        graph.add_node(self.build_node())
    }
-
-   
-   fn create_perspective_camera(graph: &mut Graph) -> Handle<Node> {
-    CameraBuilder::new(BaseBuilder::new())
-        .with_projection(Projection::Orthographic(OrthographicProjection {
-            vertical_size: 5.0,
-            z_near: 0.025,
-            z_far: 1024.0,
-        }))
-        .build(graph)
-}
-
-
 }
 
 // SkyBox builder is used to create new skybox in declarative manner.
@@ -1251,7 +1256,6 @@ impl SkyBoxBuilder {
 
        Ok(skybox)
    }
-   
 }
 
 // Skybox is a huge box around camera. Each face has its own texture, when textures are
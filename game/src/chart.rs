@@ -0,0 +1,205 @@
+//! Note charts: which lane each note of a song is in and when it has to be hit.
+//!
+//! Charts are stored as RON next to the other data files. They can be written by hand, by
+//! [`crate::chart_gen`] or by any other tool that produces the same layout.
+use crate::lanes::{Lane, LaneLayout};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    fs,
+    path::Path,
+};
+
+/// One note of a chart.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ChartNote {
+    /// Seconds from the start of the song at which the note reaches the hit line.
+    pub time: f32,
+    pub lane: Lane,
+    /// Length of a hold note in seconds, zero for a regular tap.
+    #[serde(default)]
+    pub hold: f32,
+}
+
+/// Identifies a note for the whole of a catch: its index in the chart being played. Unlike
+/// the scene node showing it, the id stays the same while nodes are spawned and freed.
+pub type NoteId = usize;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub bpm: f32,
+    /// Seconds from the start of the audio to the first beat.
+    #[serde(default)]
+    pub offset: f32,
+    #[serde(default)]
+    pub layout: LaneLayout,
+    /// Track the chart was written for, played by the chart editor.
+    #[serde(default)]
+    pub song: Option<String>,
+    /// Sorted by time once loaded.
+    pub notes: Vec<ChartNote>,
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Self {
+            bpm: 120.0,
+            offset: 0.0,
+            layout: LaneLayout::default(),
+            song: None,
+            notes: Vec::new(),
+        }
+    }
+}
+
+/// All possible errors that may occur while reading or writing a chart.
+#[derive(Debug)]
+pub enum ChartError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    /// A note is in a lane that the chart's layout does not have.
+    LaneOutOfLayout {
+        /// Index of the faulty note.
+        index: usize,
+        lane: Lane,
+        layout: LaneLayout,
+    },
+    /// BPM is zero, negative or not a number.
+    InvalidBpm(f32),
+}
+
+impl Display for ChartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChartError::Io(e) => write!(f, "Chart io error: {e}"),
+            ChartError::Parse(e) => write!(f, "Chart is malformed: {e}"),
+            ChartError::Serialize(e) => write!(f, "Unable to serialize chart: {e}"),
+            ChartError::LaneOutOfLayout {
+                index,
+                lane,
+                layout,
+            } => write!(
+                f,
+                "Note {index} is in lane {lane:?}, which is not part of the {layout:?} layout."
+            ),
+            ChartError::InvalidBpm(bpm) => write!(f, "Chart has an invalid bpm of {bpm}."),
+        }
+    }
+}
+
+impl From<std::io::Error> for ChartError {
+    fn from(e: std::io::Error) -> Self {
+        ChartError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for ChartError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        ChartError::Parse(e)
+    }
+}
+
+impl From<ron::Error> for ChartError {
+    fn from(e: ron::Error) -> Self {
+        ChartError::Serialize(e)
+    }
+}
+
+impl Chart {
+    /// Parses and validates a chart, sorting its notes by time.
+    pub fn from_ron(text: &str) -> Result<Self, ChartError> {
+        let mut chart: Chart = ron::from_str(text)?;
+        chart.validate()?;
+        chart.sort();
+        Ok(chart)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ChartError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, ChartError> {
+        Ok(ron::ser::to_string_pretty(self, PrettyConfig::default())?)
+    }
+
+    /// Writes the chart to disk, creating the parent directory if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ChartError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ChartError> {
+        if self.bpm <= 0.0 || !self.bpm.is_finite() {
+            return Err(ChartError::InvalidBpm(self.bpm));
+        }
+        for (index, note) in self.notes.iter().enumerate() {
+            if !self.layout.contains(note.lane) {
+                return Err(ChartError::LaneOutOfLayout {
+                    index,
+                    lane: note.lane,
+                    layout: self.layout,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Orders notes by time, then by lane so chords have a stable order.
+    pub fn sort(&mut self) {
+        self.notes.sort_by(|a, b| {
+            a.time
+                .total_cmp(&b.time)
+                .then(a.lane.index().cmp(&b.lane.index()))
+        });
+    }
+
+    /// Length of one beat in seconds.
+    pub fn beat_length(&self) -> f32 {
+        60.0 / self.bpm
+    }
+
+    /// Snaps a time to the closest line of a grid with `division` lines per beat, counted
+    /// from [`Self::offset`].
+    pub fn snap(&self, time: f32, division: u32) -> f32 {
+        let step = self.beat_length() / division.max(1) as f32;
+        self.offset + ((time - self.offset) / step).round() * step
+    }
+
+    /// Adds a tap note, or removes the note already in that lane within `tolerance` seconds.
+    /// Returns whether a note was added.
+    pub fn toggle_note(&mut self, time: f32, lane: Lane, tolerance: f32) -> bool {
+        let existing = self
+            .notes
+            .iter()
+            .position(|note| note.lane == lane && (note.time - time).abs() <= tolerance);
+        match existing {
+            Some(index) => {
+                self.notes.remove(index);
+                false
+            }
+            None => {
+                self.notes.push(ChartNote {
+                    time,
+                    lane,
+                    hold: 0.0,
+                });
+                self.sort();
+                true
+            }
+        }
+    }
+
+    /// Time at which the last note, including its hold, ends.
+    pub fn duration(&self) -> f32 {
+        self.notes
+            .iter()
+            .map(|note| note.time + note.hold)
+            .fold(0.0, f32::max)
+    }
+}
@@ -0,0 +1,372 @@
+//! Chart editor, opened from the catching scene to write charts without touching RON by hand.
+//!
+//! The editor owns a copy of the chart and a cursor into the song. Notes are placed at the
+//! cursor, which always sits on the beat grid, and the game keeps the scene's note blocks
+//! in sync with the chart being edited.
+use crate::{chart::Chart, lanes::Lane};
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        scroll_bar::{ScrollBarBuilder, ScrollBarMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+    keyboard::KeyCode,
+};
+
+/// Grid lines per beat the cursor can snap to.
+const SNAPS: [u32; 6] = [1, 2, 3, 4, 6, 8];
+/// Index into [`SNAPS`] the editor starts with.
+const DEFAULT_SNAP: usize = 3;
+/// Shortest timeline shown, so an empty chart still has room to write in.
+const MIN_LENGTH: f32 = 60.0;
+/// How much one press of the BPM keys changes the tempo.
+const BPM_STEP: f32 = 0.5;
+
+const HELP: &str = "1-6 toggle note | Up/Down step | PgUp/PgDn bar | Home start | [ ] snap | \
+    -/= bpm | Enter preview | Ctrl+S save | F2 close";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorAction {
+    Save,
+    Close,
+}
+
+pub struct ChartEditor {
+    root: Handle<UiNode>,
+    status: Handle<UiNode>,
+    timeline: Handle<UiNode>,
+    play_button: Handle<UiNode>,
+    save_button: Handle<UiNode>,
+    close_button: Handle<UiNode>,
+    open: bool,
+    chart: Chart,
+    path: String,
+    //cursor into the song in seconds
+    time: f32,
+    playing: bool,
+    snap: usize,
+    //the notes in the scene no longer match the chart
+    dirty: bool,
+    //the song has to jump to the cursor
+    seeked: bool,
+    //last value sent to the timeline, so its echo is not taken for a scrub
+    sent_time: f32,
+}
+
+fn make_button(text: &str, ui: &mut UserInterface) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(100.0)
+            .with_height(30.0)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(&mut ui.build_ctx())
+}
+
+impl ChartEditor {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let play_button = make_button("Preview", ui);
+        let save_button = make_button("Save", ui);
+        let close_button = make_button("Close", ui);
+
+        let ctx = &mut ui.build_ctx();
+        let status = TextBuilder::new(
+            WidgetBuilder::new()
+                .on_row(0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .build(ctx);
+        let timeline = ScrollBarBuilder::new(
+            WidgetBuilder::new()
+                .on_row(1)
+                .with_height(20.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .with_min(0.0)
+        .with_max(MIN_LENGTH)
+        .with_value(0.0)
+        .build(ctx);
+        let buttons = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .on_row(2)
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_child(play_button)
+                .with_child(save_button)
+                .with_child(close_button),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+        let help = TextBuilder::new(
+            WidgetBuilder::new()
+                .on_row(3)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_text(HELP)
+        .with_horizontal_text_alignment(HorizontalAlignment::Center)
+        .build(ctx);
+
+        let content = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_child(status)
+                .with_child(timeline)
+                .with_child(buttons)
+                .with_child(help),
+        )
+        .add_row(Row::auto())
+        .add_row(Row::auto())
+        .add_row(Row::auto())
+        .add_row(Row::auto())
+        .add_column(Column::stretch())
+        .build(ctx);
+
+        //docked to the bottom so the lanes stay visible
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_vertical_alignment(VerticalAlignment::Bottom)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(content),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            status,
+            timeline,
+            play_button,
+            save_button,
+            close_button,
+            open: false,
+            chart: Chart::default(),
+            path: String::new(),
+            time: 0.0,
+            playing: false,
+            snap: DEFAULT_SNAP,
+            dirty: false,
+            seeked: false,
+            sent_time: 0.0,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Whether the chart is being previewed, in which case it is played by autoplay.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Position of the cursor in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn chart(&self) -> &Chart {
+        &self.chart
+    }
+
+    /// Where the chart is saved to.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns true once after every change that needs the scene's notes rebuilt.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Returns true once after the cursor jumped, so a playing song can follow it.
+    pub fn take_seeked(&mut self) -> bool {
+        std::mem::take(&mut self.seeked)
+    }
+
+    pub fn open(&mut self, chart: Chart, path: String, ui: &UserInterface) {
+        self.chart = chart;
+        self.path = path;
+        self.time = 0.0;
+        self.playing = false;
+        self.dirty = true;
+        self.seeked = true;
+        self.set_visible(true, ui);
+        self.refresh(ui);
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.playing = false;
+        self.set_visible(false, ui);
+    }
+
+    fn set_visible(&mut self, visible: bool, ui: &UserInterface) {
+        self.open = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    fn division(&self) -> u32 {
+        SNAPS[self.snap]
+    }
+
+    fn step(&self) -> f32 {
+        self.chart.beat_length() / self.division() as f32
+    }
+
+    fn length(&self) -> f32 {
+        (self.chart.duration() + 4.0 * self.chart.beat_length()).max(MIN_LENGTH)
+    }
+
+    fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0.0, self.length());
+        self.seeked = true;
+        //notes hit during a preview are hidden, seeking brings them back
+        if self.playing {
+            self.dirty = true;
+        }
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        if self.playing && !playing {
+            self.dirty = true;
+        }
+        self.playing = playing;
+        self.seeked = true;
+    }
+
+    /// Moves the cursor while previewing.
+    pub fn update(&mut self, dt: f32, ui: &UserInterface) {
+        if !self.playing {
+            return;
+        }
+
+        self.time += dt;
+        if self.time >= self.length() {
+            self.time = self.length();
+            self.set_playing(false);
+        }
+        self.refresh(ui);
+    }
+
+    /// Keyboard shortcuts, see [`HELP`].
+    pub fn handle_key(
+        &mut self,
+        key: KeyCode,
+        ctrl: bool,
+        ui: &UserInterface,
+    ) -> Option<EditorAction> {
+        let beat = self.chart.beat_length();
+        let snapped = self.chart.snap(self.time, self.division());
+        match key {
+            KeyCode::KeyS if ctrl => return Some(EditorAction::Save),
+            KeyCode::F2 => return Some(EditorAction::Close),
+            KeyCode::Enter => self.set_playing(!self.playing),
+            KeyCode::ArrowUp => self.seek(snapped + self.step()),
+            KeyCode::ArrowDown => self.seek(snapped - self.step()),
+            KeyCode::PageUp => self.seek(snapped + 4.0 * beat),
+            KeyCode::PageDown => self.seek(snapped - 4.0 * beat),
+            KeyCode::Home => self.seek(0.0),
+            KeyCode::BracketLeft => self.snap = self.snap.saturating_sub(1),
+            KeyCode::BracketRight => self.snap = (self.snap + 1).min(SNAPS.len() - 1),
+            KeyCode::Minus => self.set_bpm(self.chart.bpm - BPM_STEP),
+            KeyCode::Equal => self.set_bpm(self.chart.bpm + BPM_STEP),
+            _ => {
+                let lane = match key {
+                    KeyCode::Digit1 => Lane::One,
+                    KeyCode::Digit2 => Lane::Two,
+                    KeyCode::Digit3 => Lane::Three,
+                    KeyCode::Digit4 => Lane::Four,
+                    KeyCode::Digit5 => Lane::Five,
+                    KeyCode::Digit6 => Lane::Six,
+                    _ => return None,
+                };
+                if self.chart.layout.contains(lane) {
+                    //the cursor may be off the grid while previewing, notes never are
+                    self.chart.toggle_note(snapped, lane, self.step() / 2.0);
+                    self.dirty = true;
+                }
+            }
+        }
+        self.refresh(ui);
+        None
+    }
+
+    fn set_bpm(&mut self, bpm: f32) {
+        self.chart.bpm = bpm.max(BPM_STEP);
+    }
+
+    pub fn handle_ui_message(
+        &mut self,
+        message: &UiMessage,
+        ui: &UserInterface,
+    ) -> Option<EditorAction> {
+        if let Some(ScrollBarMessage::Value(value)) = message.data() {
+            //ignores the echo of values the editor set itself
+            if message.destination() == self.timeline
+                && message.direction() == MessageDirection::FromWidget
+                && (value - self.sent_time).abs() > f32::EPSILON
+            {
+                self.seek(*value);
+                self.refresh(ui);
+            }
+        }
+
+        if let Some(ButtonMessage::Click) = message.data() {
+            if message.destination() == self.play_button {
+                self.set_playing(!self.playing);
+                self.refresh(ui);
+            } else if message.destination() == self.save_button {
+                return Some(EditorAction::Save);
+            } else if message.destination() == self.close_button {
+                return Some(EditorAction::Close);
+            }
+        }
+        None
+    }
+
+    fn refresh(&mut self, ui: &UserInterface) {
+        let beat = (self.time - self.chart.offset) / self.chart.beat_length();
+        let status = format!(
+            "{:.3}s  beat {:.2}  snap 1/{}  {:.1} bpm  {} notes  {}{}",
+            self.time,
+            beat,
+            self.division(),
+            self.chart.bpm,
+            self.chart.notes.len(),
+            self.path,
+            if self.playing { "  [previewing]" } else { "" }
+        );
+        ui.send_message(TextMessage::text(
+            self.status,
+            MessageDirection::ToWidget,
+            status,
+        ));
+
+        self.sent_time = self.time;
+        ui.send_message(ScrollBarMessage::max_value(
+            self.timeline,
+            MessageDirection::ToWidget,
+            self.length(),
+        ));
+        ui.send_message(ScrollBarMessage::value(
+            self.timeline,
+            MessageDirection::ToWidget,
+            self.time,
+        ));
+    }
+}
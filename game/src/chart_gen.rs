@@ -0,0 +1,217 @@
+//! Seeded procedural chart generator.
+//!
+//! Builds a [`Chart`] from a BPM, a duration, a lane layout, a density and a set of patterns
+//! to pick from. The same parameters always produce the same chart, so a fish without a
+//! hand-made chart still plays the same song every time.
+use crate::{
+    chart::{Chart, ChartNote},
+    lanes::{Lane, LaneLayout},
+};
+use serde::{Deserialize, Serialize};
+
+/// Building blocks the generator strings together.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    /// A lone note.
+    Single,
+    /// A run of quick notes stepping across neighbouring lanes.
+    Stream,
+    /// Two or three notes hit together.
+    Jump,
+    /// A note that is held down for a beat or two.
+    Hold,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 4] = [Pattern::Single, Pattern::Stream, Pattern::Jump, Pattern::Hold];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GeneratorParams {
+    pub seed: u64,
+    pub bpm: f32,
+    /// Length of the chart in seconds.
+    pub duration: f32,
+    pub layout: LaneLayout,
+    /// 1.0 is roughly a note per beat, higher values pack notes closer together.
+    pub density: f32,
+    /// Patterns to choose from, an empty list only produces singles.
+    pub patterns: Vec<Pattern>,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            bpm: 120.0,
+            duration: 45.0,
+            layout: LaneLayout::default(),
+            density: 1.0,
+            patterns: Pattern::ALL.to_vec(),
+        }
+    }
+}
+
+/// SplitMix64, small and stable across platforms and dependency updates, which keeps
+/// generated charts identical between builds.
+#[derive(Debug, Clone)]
+pub struct ChartRng(u64);
+
+impl ChartRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform integer in `0..bound`, `bound` must not be zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Uniform float in `0.0..1.0`.
+    pub fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Turns any string into a seed, used to give every fish its own chart.
+pub fn seed_from_str(text: &str) -> u64 {
+    //FNV-1a
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+struct Builder {
+    rng: ChartRng,
+    lanes: Vec<Lane>,
+    //time until which each lane is occupied by a hold
+    busy_until: Vec<f32>,
+    last_lane: Option<Lane>,
+    notes: Vec<ChartNote>,
+}
+
+impl Builder {
+    fn free_lanes(&self, time: f32) -> Vec<Lane> {
+        self.lanes
+            .iter()
+            .copied()
+            .filter(|lane| self.busy_until[lane.index()] <= time)
+            .collect()
+    }
+
+    //picks a free lane, avoiding the previous one when possible so singles don't jackhammer
+    fn pick_lane(&mut self, time: f32) -> Option<Lane> {
+        let mut free = self.free_lanes(time);
+        if free.len() > 1 {
+            free.retain(|lane| Some(*lane) != self.last_lane);
+        }
+        if free.is_empty() {
+            return None;
+        }
+        Some(free[self.rng.below(free.len())])
+    }
+
+    fn push(&mut self, time: f32, lane: Lane, hold: f32) {
+        self.busy_until[lane.index()] = time + hold;
+        self.last_lane = Some(lane);
+        self.notes.push(ChartNote { time, lane, hold });
+    }
+}
+
+/// Generates a chart. Notes are snapped to a quarter-beat grid and start after a two beat
+/// lead-in.
+pub fn generate(params: &GeneratorParams) -> Chart {
+    let bpm = if params.bpm > 0.0 { params.bpm } else { 120.0 };
+    let beat = 60.0 / bpm;
+    let grid = beat / 4.0;
+    let density = params.density.clamp(0.25, 4.0);
+    let patterns = if params.patterns.is_empty() {
+        vec![Pattern::Single]
+    } else {
+        params.patterns.clone()
+    };
+
+    let lanes = params.layout.lanes().to_vec();
+    let mut builder = Builder {
+        rng: ChartRng::new(params.seed),
+        busy_until: vec![f32::MIN; lanes.len()],
+        lanes,
+        last_lane: None,
+        notes: Vec::new(),
+    };
+
+    //works in whole grid steps so every note lands exactly on the grid
+    let snap = |time: f32| (time / grid).round() * grid;
+    let gap_steps = ((4.0 / density).round() as usize).max(1);
+    let stream_steps = if density >= 1.25 { 1 } else { 2 };
+
+    let mut step = 8;
+    while (step as f32 * grid) < params.duration {
+        let time = snap(step as f32 * grid);
+        match patterns[builder.rng.below(patterns.len())] {
+            Pattern::Single => {
+                if let Some(lane) = builder.pick_lane(time) {
+                    builder.push(time, lane, 0.0);
+                }
+            }
+            Pattern::Stream => {
+                let length = 4 + builder.rng.below(5);
+                let direction: i32 = if builder.rng.below(2) == 0 { 1 } else { -1 };
+                let count = builder.lanes.len() as i32;
+                let mut lane_index = builder
+                    .pick_lane(time)
+                    .map(|lane| lane.index() as i32)
+                    .unwrap_or(0);
+                for i in 0..length {
+                    let note_time = snap((step + i * stream_steps) as f32 * grid);
+                    if note_time >= params.duration {
+                        break;
+                    }
+                    let lane = builder.lanes[lane_index.rem_euclid(count) as usize];
+                    if builder.busy_until[lane.index()] <= note_time {
+                        builder.push(note_time, lane, 0.0);
+                    }
+                    lane_index += direction;
+                }
+                step += (length - 1) * stream_steps;
+            }
+            Pattern::Jump => {
+                let size = if density > 1.4 && builder.lanes.len() >= 5 { 3 } else { 2 };
+                let mut free = builder.free_lanes(time);
+                for _ in 0..size.min(free.len()) {
+                    let lane = free.remove(builder.rng.below(free.len()));
+                    builder.push(time, lane, 0.0);
+                }
+            }
+            Pattern::Hold => {
+                if let Some(lane) = builder.pick_lane(time) {
+                    let beats = 1 + builder.rng.below(2);
+                    let hold = (beats as f32 * beat).min(params.duration - time).max(0.0);
+                    builder.push(time, lane, hold);
+                }
+            }
+        }
+
+        //denser charts leave shorter gaps, with a little jitter so phrases don't feel robotic
+        let jitter = if builder.rng.unit() < 0.3 { gap_steps } else { 0 };
+        step += gap_steps + jitter;
+    }
+
+    let mut chart = Chart {
+        bpm,
+        offset: 0.0,
+        layout: params.layout,
+        song: None,
+        notes: builder.notes,
+    };
+    chart.sort();
+    chart
+}
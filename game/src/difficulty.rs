@@ -0,0 +1,98 @@
+//! Difficulty levels and the rhythm parameters they produce.
+//!
+//! The player picks a [`Difficulty`] in the settings and every fish has a difficulty rating.
+//! Both together decide how fast notes scroll, how many there are and how forgiving the hit
+//! windows are.
+use crate::{judge::HitWindows, lanes::LaneLayout};
+use fyrox::core::{reflect::prelude::*, visitor::prelude::*};
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Visit, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Parameters for a fish with a rating of 1.0.
+    pub fn base_params(self) -> RhythmParams {
+        match self {
+            Difficulty::Easy => RhythmParams {
+                scroll_speed: 3.0,
+                density: 0.5,
+                windows: HitWindows {
+                    great: 0.07,
+                    good: 0.13,
+                    ok: 0.22,
+                },
+            },
+            Difficulty::Normal => RhythmParams {
+                scroll_speed: 4.5,
+                density: 1.0,
+                windows: HitWindows {
+                    great: 0.05,
+                    good: 0.10,
+                    ok: 0.18,
+                },
+            },
+            Difficulty::Hard => RhythmParams {
+                scroll_speed: 6.0,
+                density: 1.5,
+                windows: HitWindows {
+                    great: 0.035,
+                    good: 0.07,
+                    ok: 0.14,
+                },
+            },
+        }
+    }
+
+    /// Parameters for a fish with the given rating. Harder fish scroll faster, have more notes
+    /// and tighter windows.
+    pub fn params(self, fish_rating: f32) -> RhythmParams {
+        let base = self.base_params();
+        let rating = fish_rating.max(0.1);
+        RhythmParams {
+            scroll_speed: base.scroll_speed * rating,
+            density: base.density * rating,
+            windows: HitWindows {
+                great: base.windows.great / rating,
+                good: base.windows.good / rating,
+                ok: base.windows.ok / rating,
+            },
+        }
+    }
+
+    /// Lane layout used for a fish with the given rating. Only hard fish, or any fish on
+    /// the hard difficulty, need more than the arrow keys.
+    pub fn lane_layout(self, fish_rating: f32) -> LaneLayout {
+        match self {
+            Difficulty::Hard if fish_rating >= 1.3 => LaneLayout::Six,
+            Difficulty::Hard => LaneLayout::Five,
+            _ if fish_rating >= 1.3 => LaneLayout::Five,
+            _ => LaneLayout::Four,
+        }
+    }
+}
+
+/// Everything the rhythm minigame needs to know about how hard the current catch is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RhythmParams {
+    /// How fast notes scroll towards the hit line, in world units per second.
+    pub scroll_speed: f32,
+    /// Relative amount of notes, 1.0 being a regular chart.
+    pub density: f32,
+    pub windows: HitWindows,
+}
+
+impl Default for RhythmParams {
+    fn default() -> Self {
+        Difficulty::default().base_params()
+    }
+}
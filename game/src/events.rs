@@ -0,0 +1,153 @@
+//! Things that happen in the game which other parts of it may want to react to.
+//!
+//! Anything holding the [`Game`](crate::Game) plugin can emit a [`GameEvent`]. Once per
+//! frame, part-way through the plugin's update, everything emitted since the previous
+//! publish is published: the plugin's own events of this frame up to that point, and those
+//! emitted by scripts and by the rest of the plugin's update during the previous frame.
+//!
+//! There are two ways to read them:
+//!
+//! - [`Game::events`](crate::Game::events) is the batch published this frame. The plugin
+//!   reads it right after publishing. Scripts run after the plugin, so a script reading it in
+//!   `on_update` sees the same batch, once per frame. Reading it anywhere else, or in a frame
+//!   where the script did not run, may see a batch twice or miss one.
+//! - [`Game::subscribe`](crate::Game::subscribe) hands out a [`Subscription`]. Each
+//!   [`Game::read_events`](crate::Game::read_events) on it returns every event published
+//!   since the previous read, in order, however many frames apart the reads are, so nothing
+//!   is seen twice or missed. Events are kept until every subscriber has read them, so
+//!   scripts unsubscribe in `on_deinit`.
+use crate::{chart::NoteId, judge::Judgement, lanes::Lane, scoring::CatchStats};
+use std::{collections::VecDeque, path::PathBuf};
+
+/// Something the player now owns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// A fish went into the bag.
+    Fish {
+        species: String,
+        size: f32,
+    },
+    /// A shop item, by its id in the catalogue.
+    ShopItem(String),
+    Money(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A note was hit or missed.
+    Judged {
+        note: NoteId,
+        lane: Lane,
+        judgement: Judgement,
+        /// Song time the note was due at, audio offset included.
+        hit_time: f32,
+    },
+    /// The catching minigame began. `species` is the fish on the line, if there is one.
+    CatchStarted {
+        species: Option<String>,
+        practice: bool,
+    },
+    /// The catching minigame was decided, or a practice run was ended.
+    CatchEnded {
+        won: bool,
+        stats: CatchStats,
+    },
+    ItemGained(Item),
+    /// A scene finished loading and replaced the previous one.
+    SceneChanged {
+        path: PathBuf,
+    },
+    /// A menu opened over the game or the last one closed.
+    PauseChanged {
+        paused: bool,
+    },
+}
+
+/// A reader of the [`EventBus`], see [`EventBus::subscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Subscription(usize);
+
+/// Double buffered queue of [`GameEvent`]s.
+#[derive(Debug, Default)]
+pub struct EventBus {
+    pending: Vec<GameEvent>,
+    published: Vec<GameEvent>,
+    //published events some subscriber has not read yet, the front one has number `first`
+    unread: VecDeque<GameEvent>,
+    first: usize,
+    //number of the next event each subscriber reads, `None` once it unsubscribed
+    cursors: Vec<Option<usize>>,
+}
+
+impl EventBus {
+    /// Queues an event, it is published on the next call to [`Self::publish`].
+    pub fn emit(&mut self, event: GameEvent) {
+        self.pending.push(event);
+    }
+
+    /// Makes everything emitted since the last call readable, dropping what was published
+    /// before.
+    pub fn publish(&mut self) {
+        std::mem::swap(&mut self.published, &mut self.pending);
+        self.pending.clear();
+        if self.cursors.iter().any(Option::is_some) {
+            self.unread.extend(self.published.iter().cloned());
+        } else {
+            //nobody is listening, the events only count towards the numbering
+            self.first += self.published.len();
+        }
+    }
+
+    /// Starts reading every event published from now on.
+    pub fn subscribe(&mut self) -> Subscription {
+        let cursor = Some(self.first + self.unread.len());
+        match self.cursors.iter().position(Option::is_none) {
+            Some(free) => {
+                self.cursors[free] = cursor;
+                Subscription(free)
+            }
+            None => {
+                self.cursors.push(cursor);
+                Subscription(self.cursors.len() - 1)
+            }
+        }
+    }
+
+    /// Stops reading, events the subscription has not read yet are dropped for it.
+    pub fn unsubscribe(&mut self, subscription: Subscription) {
+        if let Some(cursor) = self.cursors.get_mut(subscription.0) {
+            *cursor = None;
+        }
+        self.forget_read();
+    }
+
+    /// Every event published since the subscription last read, in the order they were
+    /// emitted. Empty for a subscription that was dropped.
+    pub fn read(&mut self, subscription: Subscription) -> Vec<GameEvent> {
+        let end = self.first + self.unread.len();
+        let Some(cursor) = self
+            .cursors
+            .get_mut(subscription.0)
+            .and_then(Option::as_mut)
+        else {
+            return Vec::new();
+        };
+        let events = self.unread.range(*cursor - self.first..).cloned().collect();
+        *cursor = end;
+        self.forget_read();
+        events
+    }
+
+    //drops the events every subscriber has read
+    fn forget_read(&mut self) {
+        let end = self.first + self.unread.len();
+        let oldest = self.cursors.iter().flatten().min().copied().unwrap_or(end);
+        self.unread.drain(..oldest - self.first);
+        self.first = oldest;
+    }
+
+    /// Events of the current frame, in the order they were emitted.
+    pub fn published(&self) -> &[GameEvent] {
+        &self.published
+    }
+}
@@ -0,0 +1,163 @@
+//! Fish species data and the player's record of what they have caught.
+use crate::scoring::Grade;
+use fyrox::core::{log::Log, rand::Rng};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Default location of the fish registry, relative to the working directory.
+pub const FISH_REGISTRY_PATH: &str = "data/fish.ron";
+/// Folder charts are looked up in when a species does not name one.
+pub const CHARTS_DIR: &str = "data/charts";
+
+/// One kind of fish that can be caught.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FishSpecies {
+    /// Stable key used in saves, never shown to the player.
+    pub id: String,
+    pub name: String,
+    pub sprite: String,
+    pub silhouette: String,
+    /// Relative chance of this species biting, compared to the others.
+    pub rarity_weight: f32,
+    pub base_price: u32,
+    /// Sizes are in centimetres.
+    pub min_size: f32,
+    pub max_size: f32,
+    /// How hard the catching minigame is for this species, 1.0 being average.
+    #[serde(default = "default_difficulty")]
+    pub difficulty: f32,
+    /// Hand-made chart for this species. Defaults to `data/charts/<id>.ron`, species without
+    /// a chart there get a generated one.
+    #[serde(default)]
+    pub chart: Option<String>,
+}
+
+fn default_difficulty() -> f32 {
+    1.0
+}
+
+impl FishSpecies {
+    /// Where the chart of this species is read from and saved to.
+    pub fn chart_path(&self) -> String {
+        self.chart
+            .clone()
+            .unwrap_or_else(|| format!("{CHARTS_DIR}/{}.ron", self.id))
+    }
+}
+
+/// Every species in the game, in the order they are listed in the collection log.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FishRegistry {
+    pub species: Vec<FishSpecies>,
+}
+
+impl FishRegistry {
+    /// Reads the registry, logging and returning an empty one if the file is broken.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|e| e.to_string()));
+        match result {
+            Ok(registry) => registry,
+            Err(e) => {
+                Log::err(format!("Unable to load fish registry {}: {e}", path.display()));
+                Self::default()
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&FishSpecies> {
+        self.species.iter().find(|species| species.id == id)
+    }
+
+    /// Picks a species weighted by rarity and a size within its range.
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<(&FishSpecies, f32)> {
+        let total: f32 = self.species.iter().map(|s| s.rarity_weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut pick = rng.gen_range(0.0..total);
+        for species in self.species.iter() {
+            if pick < species.rarity_weight {
+                let size = rng.gen_range(species.min_size..=species.max_size);
+                return Some((species, size));
+            }
+            pick -= species.rarity_weight;
+        }
+        self.species.last().map(|s| (s, s.max_size))
+    }
+}
+
+/// Rough weight in grams of a fish of the given length in centimetres, using the usual
+/// length-cubed rule of thumb.
+pub fn weight_grams(size: f32) -> f32 {
+    size * size * size / 100.0
+}
+
+/// Everything remembered about one species the player has caught.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatchRecord {
+    pub count: u32,
+    pub largest_size: f32,
+    pub best_grade: Option<Grade>,
+    /// Unix timestamp, in seconds, of the first catch.
+    pub first_caught: u64,
+}
+
+/// The player's collection, keyed by species id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FishLog {
+    pub records: BTreeMap<String, CatchRecord>,
+}
+
+impl FishLog {
+    pub fn get(&self, id: &str) -> Option<&CatchRecord> {
+        self.records.get(id)
+    }
+
+    /// Adds a catch and returns whether it beat the previous largest size of its species.
+    pub fn record(&mut self, id: &str, size: f32, grade: Option<Grade>, now: u64) -> bool {
+        match self.records.get_mut(id) {
+            Some(record) => {
+                record.count += 1;
+                record.best_grade = record.best_grade.max(grade);
+                if size > record.largest_size {
+                    record.largest_size = size;
+                    true
+                } else {
+                    false
+                }
+            }
+            None => {
+                self.records.insert(
+                    id.to_string(),
+                    CatchRecord {
+                        count: 1,
+                        largest_size: size,
+                        best_grade: grade,
+                        first_caught: now,
+                    },
+                );
+                true
+            }
+        }
+    }
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD` (UTC).
+pub fn format_date(unix_secs: u64) -> String {
+    //days-to-civil conversion from Howard Hinnant's date algorithms
+    let days = (unix_secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}")
+}
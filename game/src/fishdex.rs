@@ -0,0 +1,237 @@
+//! Collection screen listing every species and what the player knows about it.
+use crate::fish::{self, FishLog, FishRegistry};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        image::{ImageBuilder, ImageMessage},
+        message::{MessageDirection, UiMessage},
+        scroll_viewer::ScrollViewerBuilder,
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+    resource::texture::Texture,
+};
+
+const COLUMNS: [(&str, f32); 6] = [
+    ("", 56.0),
+    ("Species", 180.0),
+    ("Caught", 70.0),
+    ("Largest", 90.0),
+    ("Best", 60.0),
+    ("First caught", 120.0),
+];
+
+struct SpeciesRow {
+    id: String,
+    image: Handle<UiNode>,
+    name: Handle<UiNode>,
+    count: Handle<UiNode>,
+    largest: Handle<UiNode>,
+    grade: Handle<UiNode>,
+    date: Handle<UiNode>,
+}
+
+pub struct Fishdex {
+    root: Handle<UiNode>,
+    rows: Vec<SpeciesRow>,
+    back: Handle<UiNode>,
+    open: bool,
+}
+
+fn make_cell(ctx: &mut BuildContext, row: usize, column: usize) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(column)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_vertical_text_alignment(VerticalAlignment::Center)
+    .build(ctx)
+}
+
+impl Fishdex {
+    pub fn new(ui: &mut UserInterface, registry: &FishRegistry) -> Self {
+        let ctx = &mut ui.build_ctx();
+
+        let mut children = Vec::new();
+        for (column, (header, _)) in COLUMNS.iter().enumerate() {
+            children.push(
+                TextBuilder::new(WidgetBuilder::new().on_row(0).on_column(column))
+                    .with_text(*header)
+                    .build(ctx),
+            );
+        }
+
+        //row 0 is the header, species start from row 1
+        let mut rows = Vec::new();
+        for (i, species) in registry.species.iter().enumerate() {
+            let row = i + 1;
+            let image = ImageBuilder::new(
+                WidgetBuilder::new()
+                    .on_row(row)
+                    .on_column(0)
+                    .with_width(48.0)
+                    .with_height(48.0),
+            )
+            .build(ctx);
+            let species_row = SpeciesRow {
+                id: species.id.clone(),
+                image,
+                name: make_cell(ctx, row, 1),
+                count: make_cell(ctx, row, 2),
+                largest: make_cell(ctx, row, 3),
+                grade: make_cell(ctx, row, 4),
+                date: make_cell(ctx, row, 5),
+            };
+            children.extend([
+                species_row.image,
+                species_row.name,
+                species_row.count,
+                species_row.largest,
+                species_row.grade,
+                species_row.date,
+            ]);
+            rows.push(species_row);
+        }
+
+        let mut grid = GridBuilder::new(WidgetBuilder::new().with_children(children))
+            .add_row(Row::strict(24.0))
+            .add_rows(registry.species.iter().map(|_| Row::strict(52.0)).collect());
+        for (_, width) in COLUMNS.iter() {
+            grid = grid.add_column(Column::strict(*width));
+        }
+        let grid = grid.build(ctx);
+
+        let list = ScrollViewerBuilder::new(WidgetBuilder::new().with_height(420.0))
+            .with_content(grid)
+            .build(ctx);
+
+        let title = TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(8.0)))
+            .with_text("Fishdex")
+            .with_horizontal_text_alignment(HorizontalAlignment::Center)
+            .build(ctx);
+
+        let back = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(120.0)
+                .with_height(36.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_text("Back")
+        .build(ctx);
+
+        let content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(title)
+                .with_child(list)
+                .with_child(back),
+        )
+        .build(ctx);
+
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(content),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            rows,
+            back,
+            open: false,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows the screen with the latest catches filled in.
+    pub fn open(
+        &mut self,
+        registry: &FishRegistry,
+        log: &FishLog,
+        resource_manager: &ResourceManager,
+        ui: &UserInterface,
+    ) {
+        for row in self.rows.iter() {
+            let Some(species) = registry.get(&row.id) else {
+                continue;
+            };
+            let record = log.get(&row.id);
+
+            //uncaught species only show their silhouette
+            let (texture, name, count, largest, grade, date) = match record {
+                Some(record) => (
+                    &species.sprite,
+                    species.name.clone(),
+                    record.count.to_string(),
+                    format!("{:.1} cm", record.largest_size),
+                    record
+                        .best_grade
+                        .map(|g| format!("{g:?}"))
+                        .unwrap_or_else(|| "-".to_string()),
+                    fish::format_date(record.first_caught),
+                ),
+                None => (
+                    &species.silhouette,
+                    "???".to_string(),
+                    "0".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                    "-".to_string(),
+                ),
+            };
+
+            ui.send_message(ImageMessage::texture(
+                row.image,
+                MessageDirection::ToWidget,
+                Some(resource_manager.request::<Texture>(texture).into_untyped()),
+            ));
+            for (cell, text) in [
+                (row.name, name),
+                (row.count, count),
+                (row.largest, largest),
+                (row.grade, grade),
+                (row.date, date),
+            ] {
+                ui.send_message(TextMessage::text(cell, MessageDirection::ToWidget, text));
+            }
+        }
+
+        self.set_visible(true, ui);
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.set_visible(false, ui);
+    }
+
+    fn set_visible(&mut self, visible: bool, ui: &UserInterface) {
+        self.open = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    /// Returns true if the player asked to close the screen.
+    pub fn handle_ui_message(&self, message: &UiMessage) -> bool {
+        matches!(message.data(), Some(ButtonMessage::Click)) && message.destination() == self.back
+    }
+}
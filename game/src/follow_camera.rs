@@ -0,0 +1,188 @@
+//! Script that keeps a 2D camera on the player.
+//!
+//! The player can move around a dead zone in the middle of the screen without the camera
+//! moving. Once they leave it the camera eases after them, looking a little ahead in the
+//! direction they walk, and never shows anything outside of the map bounds.
+use crate::{camera, player, Game};
+use fyrox::{
+    core::{
+        algebra::{Vector2, Vector3},
+        pool::Handle,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        visitor::prelude::*,
+        TypeUuidProvider,
+    },
+    impl_component_provider,
+    scene::{
+        camera::{Camera, Projection},
+        node::Node,
+    },
+    script::{ScriptContext, ScriptTrait},
+};
+
+/// Moves the node it is attached to after a target, meant for a camera at the root of the
+/// scene.
+#[derive(Visit, Reflect, Debug, Clone)]
+pub struct FollowCamera {
+    /// Node to follow, the player when left empty.
+    target: Handle<Node>,
+    /// Half the width and height of the box around the middle of the screen the target can
+    /// move in without the camera following, in world units.
+    dead_zone: Vector2<f32>,
+    /// How quickly the camera catches up, higher is snappier and zero snaps right away.
+    smoothing: f32,
+    /// How far ahead of a moving target the camera looks, in world units.
+    look_ahead: f32,
+    /// Corners of the map. The camera keeps its view inside them, unless they are left
+    /// equal.
+    bounds_min: Vector2<f32>,
+    bounds_max: Vector2<f32>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    look: Vector2<f32>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    last_target: Option<Vector2<f32>>,
+}
+
+impl Default for FollowCamera {
+    fn default() -> Self {
+        Self {
+            target: Handle::NONE,
+            dead_zone: Vector2::new(1.0, 0.75),
+            smoothing: 5.0,
+            look_ahead: 1.5,
+            bounds_min: Vector2::default(),
+            bounds_max: Vector2::default(),
+            look: Vector2::default(),
+            last_target: None,
+        }
+    }
+}
+
+impl_component_provider!(FollowCamera);
+
+impl TypeUuidProvider for FollowCamera {
+    // Returns unique script id for serialization needs.
+    fn type_uuid() -> Uuid {
+        uuid!("4f0c6a52-8d57-4b8e-9d3a-6a1f2e7c5b91")
+    }
+}
+
+//half the width and height of what a 2d camera shows, in world units, for both our camera
+//and the stock one
+fn view_half_size(node: &Node) -> Option<Vector2<f32>> {
+    let matrix = if let Some(camera) = node.cast::<camera::Camera>() {
+        let camera::Projection::Orthographic(_) = camera.projection() else {
+            return None;
+        };
+        camera.projection_matrix()
+    } else {
+        let camera = node.cast::<Camera>()?;
+        let Projection::Orthographic(_) = camera.projection() else {
+            return None;
+        };
+        camera.projection_matrix()
+    };
+    (matrix[(0, 0)] > 0.0 && matrix[(1, 1)] > 0.0)
+        .then(|| Vector2::new(1.0 / matrix[(0, 0)], 1.0 / matrix[(1, 1)]))
+}
+
+//moves `position` towards the middle of `min..max` until the view fits, centring it if the
+//view is larger than the bounds
+fn clamp_axis(position: f32, half_view: f32, min: f32, max: f32) -> f32 {
+    if max - min <= 2.0 * half_view {
+        (min + max) / 2.0
+    } else {
+        position.clamp(min + half_view, max - half_view)
+    }
+}
+
+impl FollowCamera {
+    fn has_bounds(&self) -> bool {
+        self.bounds_max.x > self.bounds_min.x && self.bounds_max.y > self.bounds_min.y
+    }
+}
+
+impl ScriptTrait for FollowCamera {
+    fn on_start(&mut self, context: &mut ScriptContext) {
+        //starts on the target instead of sweeping over from wherever the camera was placed
+        let graph = &mut context.scene.graph;
+        if self.target.is_none() {
+            self.target = player::find_player(graph);
+        }
+        if let Some(target) = graph
+            .try_get(self.target)
+            .map(|node| node.global_position())
+        {
+            let transform = graph[context.handle].local_transform_mut();
+            let z = transform.position().z;
+            transform.set_position(Vector3::new(target.x, target.y, z));
+            self.last_target = Some(target.xy());
+        }
+    }
+
+    fn on_update(&mut self, context: &mut ScriptContext) {
+        //holds still while a menu is open
+        if context.plugins.get::<Game>().is_paused() || context.dt <= 0.0 {
+            return;
+        }
+        let graph = &mut context.scene.graph;
+        let Some(target) = graph
+            .try_get(self.target)
+            .map(|node| node.global_position().xy())
+        else {
+            return;
+        };
+        let ease = if self.smoothing > 0.0 {
+            1.0 - (-self.smoothing * context.dt).exp()
+        } else {
+            1.0
+        };
+
+        //looks ahead in the direction the target is moving, and back to it when it stops
+        let velocity = self
+            .last_target
+            .map_or(Vector2::default(), |last| (target - last) / context.dt);
+        self.last_target = Some(target);
+        let ahead = velocity
+            .try_normalize(f32::EPSILON)
+            .map_or(Vector2::default(), |direction| direction * self.look_ahead);
+        self.look += (ahead - self.look) * ease;
+        let focus = target + self.look;
+
+        //only follows as far as needed to keep the focus inside the dead zone
+        let half_view = view_half_size(&graph[context.handle]);
+        let transform = graph[context.handle].local_transform_mut();
+        let position = transform.position().clone();
+        let mut desired = position.xy();
+        for axis in 0..2 {
+            let offset = focus[axis] - desired[axis];
+            let zone = self.dead_zone[axis].max(0.0);
+            if offset > zone {
+                desired[axis] += offset - zone;
+            } else if offset < -zone {
+                desired[axis] += offset + zone;
+            }
+        }
+        let mut next = position.xy() + (desired - position.xy()) * ease;
+
+        if let Some(half_view) = half_view.filter(|_| self.has_bounds()) {
+            for axis in 0..2 {
+                next[axis] = clamp_axis(
+                    next[axis],
+                    half_view[axis],
+                    self.bounds_min[axis],
+                    self.bounds_max[axis],
+                );
+            }
+        }
+        transform.set_position(Vector3::new(next.x, next.y, position.z));
+    }
+
+    // Returns unique script ID for serialization needs.
+    fn id(&self) -> Uuid {
+        Self::type_uuid()
+    }
+}
@@ -0,0 +1,320 @@
+//! Visual feedback for judgements: a popup naming the judgement, a flash of the lane's
+//! hit-line block, a burst of sparks and a combo counter that pops on every hit.
+//!
+//! Everything is driven by [`GameEvent::Judged`], so played, autoplayed and replayed catches
+//! all look the same. The combo itself is the catch session's, the counter only shows it.
+use crate::{events::GameEvent, judge::Judgement};
+use fyrox::{
+    core::{
+        algebra::{Matrix3, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    gui::{
+        brush::Brush,
+        message::MessageDirection,
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+    scene::{
+        base::BaseBuilder,
+        dim2::rectangle::{Rectangle, RectangleBuilder},
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+
+/// Seconds a judgement popup stays up.
+const POPUP_TIME: f32 = 0.6;
+/// Seconds the popup and the combo counter take to shrink back after popping.
+const POP_TIME: f32 = 0.12;
+const POPUP_SCALE: f32 = 2.0;
+const COMBO_SCALE: f32 = 1.5;
+/// How much bigger than normal text is the moment it pops.
+const POP_GROWTH: f32 = 0.4;
+/// Seconds a hit-line block takes to fade back from its flash.
+const FLASH_TIME: f32 = 0.2;
+const SPARK_COUNT: usize = 6;
+const SPARK_TIME: f32 = 0.35;
+/// World units per second the sparks fly apart at.
+const SPARK_SPEED: f32 = 3.0;
+const SPARK_SIZE: f32 = 0.15;
+/// Shortest combo the counter is shown for.
+const MIN_COMBO: u32 = 2;
+
+fn judgement_text(judgement: Judgement) -> &'static str {
+    match judgement {
+        Judgement::Great => "GREAT!",
+        Judgement::Good => "GOOD",
+        Judgement::Ok => "OK",
+        Judgement::Miss => "MISS",
+    }
+}
+
+fn judgement_color(judgement: Judgement) -> Color {
+    match judgement {
+        Judgement::Great => Color::from_rgba(255, 215, 64, 255),
+        Judgement::Good => Color::from_rgba(96, 220, 96, 255),
+        Judgement::Ok => Color::from_rgba(96, 170, 255, 255),
+        Judgement::Miss => Color::from_rgba(230, 64, 64, 255),
+    }
+}
+
+//scale of text `age` seconds after it popped
+fn pop_scale(scale: f32, age: f32) -> f32 {
+    scale * (1.0 + POP_GROWTH * (1.0 - age / POP_TIME).max(0.0))
+}
+
+struct Flash {
+    block: Handle<Node>,
+    color: Color,
+    age: f32,
+}
+
+struct Spark {
+    node: Handle<Node>,
+    velocity: Vector3<f32>,
+    age: f32,
+}
+
+pub struct HitFeedback {
+    root: Handle<UiNode>,
+    popup: Handle<UiNode>,
+    combo: Handle<UiNode>,
+    //seconds since the popup and the combo counter last popped
+    popup_age: f32,
+    combo_age: f32,
+    flashes: Vec<Flash>,
+    sparks: Vec<Spark>,
+}
+
+impl HitFeedback {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let ctx = &mut ui.build_ctx();
+        let popup = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_horizontal_text_alignment(HorizontalAlignment::Center)
+        .build(ctx);
+        let combo = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_horizontal_text_alignment(HorizontalAlignment::Center)
+        .build(ctx);
+
+        //sits above the lanes and never takes clicks away from the menus
+        let root = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_hit_test_visibility(false)
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_margin(Thickness::top(80.0))
+                .with_child(popup)
+                .with_child(combo),
+        )
+        .build(ctx);
+
+        let mut feedback = Self {
+            root,
+            popup,
+            combo,
+            popup_age: POPUP_TIME,
+            combo_age: POP_TIME,
+            flashes: Vec::new(),
+            sparks: Vec::new(),
+        };
+        feedback.reset(ui);
+        feedback
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    /// Hides everything. Scene nodes are forgotten, as they went away with the scene.
+    pub fn reset(&mut self, ui: &UserInterface) {
+        self.popup_age = POPUP_TIME;
+        self.combo_age = POP_TIME;
+        self.flashes.clear();
+        self.sparks.clear();
+        set_visible(self.popup, false, ui);
+        set_visible(self.combo, false, ui);
+        set_scale(self.combo, COMBO_SCALE, ui);
+    }
+
+    /// Reacts to this frame's judgements and moves the running effects along. `references`
+    /// are the hit-line blocks of the lanes and `combo` the session's combo once this frame's
+    /// judgements are counted.
+    pub fn update(
+        &mut self,
+        events: &[GameEvent],
+        combo: u32,
+        scene: Option<&mut Scene>,
+        references: &[Handle<Node>],
+        dt: f32,
+        ui: &UserInterface,
+    ) {
+        let Some(scene) = scene else {
+            return;
+        };
+        let mut judged = false;
+        for event in events {
+            if let GameEvent::Judged {
+                lane, judgement, ..
+            } = event
+            {
+                judged = true;
+                self.show_judgement(*judgement, ui);
+                if let Some(block) = references.get(lane.index()) {
+                    self.flash(scene, *block, *judgement);
+                }
+            }
+        }
+        if judged {
+            self.show_combo(combo, ui);
+        }
+
+        //each animation sends one last message once it is over, then leaves the widget be
+        if self.popup_age < POPUP_TIME {
+            self.popup_age += dt;
+            if self.popup_age < POPUP_TIME {
+                set_scale(self.popup, pop_scale(POPUP_SCALE, self.popup_age), ui);
+                //fades out over the second half
+                let opacity = (2.0 * (1.0 - self.popup_age / POPUP_TIME)).min(1.0);
+                ui.send_message(WidgetMessage::opacity(
+                    self.popup,
+                    MessageDirection::ToWidget,
+                    Some(opacity),
+                ));
+            } else {
+                set_visible(self.popup, false, ui);
+            }
+        }
+        if self.combo_age < POP_TIME {
+            self.combo_age += dt;
+            set_scale(self.combo, pop_scale(COMBO_SCALE, self.combo_age), ui);
+        }
+
+        for flash in self.flashes.iter_mut() {
+            flash.age += dt;
+            if let Some(rectangle) = scene
+                .graph
+                .try_get_mut(flash.block)
+                .and_then(|node| node.cast_mut::<Rectangle>())
+            {
+                let t = (flash.age / FLASH_TIME).min(1.0);
+                rectangle.set_color(flash.color.lerp(Color::WHITE, t));
+            }
+        }
+        self.flashes.retain(|flash| flash.age < FLASH_TIME);
+
+        for spark in self.sparks.iter_mut() {
+            spark.age += dt;
+            if let Some(node) = scene.graph.try_get_mut(spark.node) {
+                let size = SPARK_SIZE * (1.0 - spark.age / SPARK_TIME).max(0.0);
+                let transform = node.local_transform_mut();
+                transform.offset(spark.velocity * dt);
+                transform.set_scale(Vector3::new(size, size, 1.0));
+            }
+        }
+        for spark in self.sparks.iter().filter(|spark| spark.age >= SPARK_TIME) {
+            if scene.graph.is_valid_handle(spark.node) {
+                scene.graph.remove_node(spark.node);
+            }
+        }
+        self.sparks.retain(|spark| spark.age < SPARK_TIME);
+    }
+
+    fn show_judgement(&mut self, judgement: Judgement, ui: &UserInterface) {
+        self.popup_age = 0.0;
+        ui.send_message(TextMessage::text(
+            self.popup,
+            MessageDirection::ToWidget,
+            judgement_text(judgement).to_string(),
+        ));
+        ui.send_message(WidgetMessage::foreground(
+            self.popup,
+            MessageDirection::ToWidget,
+            Brush::Solid(judgement_color(judgement)),
+        ));
+        set_visible(self.popup, true, ui);
+
+        //every hit pops the counter
+        if judgement != Judgement::Miss {
+            self.combo_age = 0.0;
+        }
+    }
+
+    //a miss drops the counter to zero, which hides it
+    fn show_combo(&self, combo: u32, ui: &UserInterface) {
+        set_visible(self.combo, combo >= MIN_COMBO, ui);
+        ui.send_message(TextMessage::text(
+            self.combo,
+            MessageDirection::ToWidget,
+            format!("{combo} combo"),
+        ));
+    }
+
+    //tints the hit-line block and sends sparks flying from it on a hit
+    fn flash(&mut self, scene: &mut Scene, block: Handle<Node>, judgement: Judgement) {
+        let Some(position) = scene
+            .graph
+            .try_get(block)
+            .map(|block| block.local_transform().position().clone())
+        else {
+            return;
+        };
+        let color = judgement_color(judgement);
+        self.flashes.retain(|flash| flash.block != block);
+        self.flashes.push(Flash {
+            block,
+            color,
+            age: 0.0,
+        });
+        if judgement == Judgement::Miss {
+            return;
+        }
+
+        for i in 0..SPARK_COUNT {
+            let angle = std::f32::consts::TAU * i as f32 / SPARK_COUNT as f32;
+            let node = RectangleBuilder::new(
+                BaseBuilder::new().with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(position)
+                        .with_local_scale(Vector3::new(SPARK_SIZE, SPARK_SIZE, 1.0))
+                        .build(),
+                ),
+            )
+            .with_color(color)
+            .build(&mut scene.graph);
+            self.sparks.push(Spark {
+                node,
+                velocity: Vector3::new(angle.cos(), angle.sin(), 0.0) * SPARK_SPEED,
+                age: 0.0,
+            });
+        }
+    }
+}
+
+fn set_visible(widget: Handle<UiNode>, visible: bool, ui: &UserInterface) {
+    ui.send_message(WidgetMessage::visibility(
+        widget,
+        MessageDirection::ToWidget,
+        visible,
+    ));
+}
+
+fn set_scale(widget: Handle<UiNode>, scale: f32, ui: &UserInterface) {
+    ui.send_message(WidgetMessage::layout_transform(
+        widget,
+        MessageDirection::ToWidget,
+        Matrix3::new_scaling(scale),
+    ));
+}
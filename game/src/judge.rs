@@ -0,0 +1,250 @@
+//! Decides which note a key press hits and how well.
+//!
+//! Every note tells the judge at what song time it should be hit. A press in a lane judges the
+//! closest unjudged note of that lane inside the hit window, so any number of notes can be
+//! hittable at once and chords work by pressing several lanes together.
+//!
+//! A hold note is judged once, when it is over: the press on its head decides the judgement
+//! and the key then has to stay down until the end of the hold. Letting go more than the Ok
+//! window before the end misses the whole note.
+//!
+//! Everything is decided from song times, never from frame timings, so feeding the same
+//! presses at the same times always gives the same judgements.
+//!
+//! Notes are told apart by a key of the caller's choosing, such as the scene node showing
+//! them or their index in the chart.
+use crate::lanes::Lane;
+use std::{collections::HashMap, hash::Hash};
+
+/// How early or late, in seconds, a press may be for each judgement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitWindows {
+    pub great: f32,
+    pub good: f32,
+    /// Outside of this a note cannot be hit at all.
+    pub ok: f32,
+}
+
+impl Default for HitWindows {
+    fn default() -> Self {
+        Self {
+            great: 0.05,
+            good: 0.10,
+            ok: 0.18,
+        }
+    }
+}
+
+impl HitWindows {
+    /// Judgement for a press made `offset` seconds away from the note, or `None` if the note
+    /// is too far away to be hit.
+    pub fn judge(&self, offset: f32) -> Option<Judgement> {
+        match offset.abs() {
+            d if d <= self.great => Some(Judgement::Great),
+            d if d <= self.good => Some(Judgement::Good),
+            d if d <= self.ok => Some(Judgement::Ok),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Judgement {
+    Great,
+    Good,
+    Ok,
+    Miss,
+}
+
+/// A judgement together with the note it was given to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Judged<K> {
+    pub note: K,
+    pub lane: Lane,
+    /// Song time at which the note should have been hit, the head of a hold.
+    pub hit_time: f32,
+    pub judgement: Judgement,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TrackedNote {
+    lane: Lane,
+    //song time at which the note is perfectly on the hit line
+    hit_time: f32,
+    //length of a hold in seconds, zero for a tap
+    hold: f32,
+    //judgement of the head of a hold while its key is held down
+    head: Option<Judgement>,
+    judged: bool,
+    //when the note was first tracked, breaks ties between notes due at the same time
+    order: usize,
+}
+
+#[derive(Debug)]
+pub struct NoteJudge<K> {
+    windows: HitWindows,
+    notes: HashMap<K, TrackedNote>,
+    results: HashMap<K, Judgement>,
+    //every judgement in the order it was made
+    history: Vec<Judged<K>>,
+}
+
+impl<K> Default for NoteJudge<K> {
+    fn default() -> Self {
+        Self {
+            windows: HitWindows::default(),
+            notes: HashMap::new(),
+            results: HashMap::new(),
+            history: Vec::new(),
+        }
+    }
+}
+
+impl<K: Copy + Eq + Hash> NoteJudge<K> {
+    pub fn windows(&self) -> HitWindows {
+        self.windows
+    }
+
+    pub fn set_windows(&mut self, windows: HitWindows) {
+        self.windows = windows;
+    }
+
+    /// Forgets every note, used when a new catch starts.
+    pub fn clear(&mut self) {
+        self.notes.clear();
+        self.results.clear();
+        self.history.clear();
+    }
+
+    /// Registers a tap note, or updates when it should be hit.
+    pub fn track(&mut self, note: K, lane: Lane, hit_time: f32) {
+        self.track_hold(note, lane, hit_time, 0.0);
+    }
+
+    /// Registers a note held for `hold` seconds after `hit_time`, or updates it. A `hold` of
+    /// zero makes it a tap.
+    pub fn track_hold(&mut self, note: K, lane: Lane, hit_time: f32, hold: f32) {
+        let order = self.notes.len();
+        let tracked = self.notes.entry(note).or_insert(TrackedNote {
+            lane,
+            hit_time,
+            hold,
+            head: None,
+            judged: false,
+            order,
+        });
+        tracked.lane = lane;
+        tracked.hit_time = hit_time;
+        tracked.hold = hold;
+    }
+
+    /// Judges the closest hittable note in the lane for a press at song time `time`. Presses
+    /// with nothing in reach are ignored. A hold only takes the judgement of its head for
+    /// now, it is booked once the hold is over.
+    pub fn press(&mut self, lane: Lane, time: f32) -> Option<Judgement> {
+        let windows = self.windows;
+        let (note, tracked) = self
+            .notes
+            .iter_mut()
+            .filter(|(_, n)| {
+                !n.judged
+                    && n.head.is_none()
+                    && n.lane == lane
+                    && windows.judge(n.hit_time - time).is_some()
+            })
+            //notes equally far away go to the earlier one, then to the one tracked first, never
+            //to whichever the map happens to list first
+            .min_by(|(_, a), (_, b)| {
+                (a.hit_time - time)
+                    .abs()
+                    .total_cmp(&(b.hit_time - time).abs())
+                    .then(a.hit_time.total_cmp(&b.hit_time))
+                    .then(a.order.cmp(&b.order))
+            })?;
+
+        let judgement = windows.judge(tracked.hit_time - time)?;
+        if tracked.hold > 0.0 {
+            tracked.head = Some(judgement);
+            return Some(judgement);
+        }
+        tracked.judged = true;
+        self.results.insert(*note, judgement);
+        self.history.push(Judged {
+            note: *note,
+            lane,
+            hit_time: tracked.hit_time,
+            judgement,
+        });
+        Some(judgement)
+    }
+
+    /// Lets go of the hold being held in the lane at song time `time`. Holds let go of before
+    /// their end are missed. Returns the judgement of the hold, if one was held.
+    pub fn release(&mut self, lane: Lane, time: f32) -> Option<Judgement> {
+        let ok = self.windows.ok;
+        let (note, tracked) = self
+            .notes
+            .iter_mut()
+            .filter(|(_, n)| !n.judged && n.head.is_some() && n.lane == lane)
+            .min_by(|(_, a), (_, b)| {
+                a.hit_time
+                    .total_cmp(&b.hit_time)
+                    .then(a.order.cmp(&b.order))
+            })?;
+
+        let judgement = if time >= tracked.hit_time + tracked.hold - ok {
+            tracked.head?
+        } else {
+            Judgement::Miss
+        };
+        tracked.judged = true;
+        self.results.insert(*note, judgement);
+        self.history.push(Judged {
+            note: *note,
+            lane,
+            hit_time: tracked.hit_time,
+            judgement,
+        });
+        Some(judgement)
+    }
+
+    /// Turns every note that can no longer be hit at song time `time` into a miss, and books
+    /// the holds that were held until their end. Called after all inputs up to that time
+    /// went in.
+    pub fn expire(&mut self, time: f32) {
+        let mut judged = Vec::new();
+        for (note, tracked) in self.notes.iter_mut() {
+            if tracked.judged {
+                continue;
+            }
+            let judgement = match tracked.head {
+                Some(head) if time >= tracked.hit_time + tracked.hold => head,
+                None if time - tracked.hit_time > self.windows.ok => Judgement::Miss,
+                _ => continue,
+            };
+            tracked.judged = true;
+            self.results.insert(*note, judgement);
+            let entry = Judged {
+                note: *note,
+                lane: tracked.lane,
+                hit_time: tracked.hit_time,
+                judgement,
+            };
+            judged.push((tracked.order, entry));
+        }
+        //in the order the notes came by, not the order of the map
+        judged.sort_by(|(a, x), (b, y)| x.hit_time.total_cmp(&y.hit_time).then(a.cmp(b)));
+        self.history
+            .extend(judged.into_iter().map(|(_, entry)| entry));
+    }
+
+    /// Hands a note its judgement once, after it has been hit or missed.
+    pub fn take_result(&mut self, note: K) -> Option<Judgement> {
+        self.results.remove(&note)
+    }
+
+    /// Drains every judgement made since the last call.
+    pub fn take_judged(&mut self) -> Vec<Judged<K>> {
+        std::mem::take(&mut self.history)
+    }
+}
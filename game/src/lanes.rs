@@ -0,0 +1,143 @@
+//! Note lanes of the rhythm minigame: how many there are, where they sit and which key hits
+//! each of them.
+use fyrox::{
+    core::{reflect::prelude::*, visitor::prelude::*},
+    keyboard::KeyCode,
+};
+use serde::{Deserialize, Serialize};
+
+/// Horizontal distance between the centres of two neighbouring lanes, in world units.
+pub const LANE_SPACING: f32 = 1.0;
+
+const LEFT_SPRITE: &str = "data/data/objects/left_fish.png";
+const DOWN_SPRITE: &str = "data/data/objects/down_fish.png";
+const UP_SPRITE: &str = "data/data/objects/up_fish.png";
+const RIGHT_SPRITE: &str = "data/data/objects/right_fish.png";
+const HOOK_SPRITE: &str = "data/data/objects/fishhook.png";
+
+/// One column notes scroll down, numbered from the left.
+#[derive(
+    Visit, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+pub enum Lane {
+    #[default]
+    One,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 6] = [
+        Lane::One,
+        Lane::Two,
+        Lane::Three,
+        Lane::Four,
+        Lane::Five,
+        Lane::Six,
+    ];
+
+    /// Zero-based position from the left.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Lane> {
+        Self::ALL.get(index).copied()
+    }
+}
+
+/// How many lanes a chart uses. Harder fish use wider layouts.
+#[derive(
+    Visit, Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+pub enum LaneLayout {
+    /// Arrow keys: left, down, up, right.
+    #[default]
+    Four,
+    /// Arrow keys with space in the middle.
+    Five,
+    /// Home row: S D F and J K L.
+    Six,
+}
+
+impl LaneLayout {
+    pub fn lane_count(self) -> usize {
+        match self {
+            LaneLayout::Four => 4,
+            LaneLayout::Five => 5,
+            LaneLayout::Six => 6,
+        }
+    }
+
+    /// Lanes of this layout, from left to right.
+    pub fn lanes(self) -> &'static [Lane] {
+        &Lane::ALL[..self.lane_count()]
+    }
+
+    pub fn contains(self, lane: Lane) -> bool {
+        lane.index() < self.lane_count()
+    }
+
+    /// X coordinate of the lane centre, with the whole layout centred on zero.
+    pub fn x_position(self, lane: Lane) -> f32 {
+        let middle = (self.lane_count() as f32 - 1.0) / 2.0;
+        (lane.index() as f32 - middle) * LANE_SPACING
+    }
+
+    /// Key that hits notes in the given lane, or `None` if the lane is not part of the layout.
+    pub fn key(self, lane: Lane) -> Option<KeyCode> {
+        let keys: &[KeyCode] = match self {
+            LaneLayout::Four => &[
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowUp,
+                KeyCode::ArrowRight,
+            ],
+            LaneLayout::Five => &[
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowDown,
+                KeyCode::Space,
+                KeyCode::ArrowUp,
+                KeyCode::ArrowRight,
+            ],
+            LaneLayout::Six => &[
+                KeyCode::KeyS,
+                KeyCode::KeyD,
+                KeyCode::KeyF,
+                KeyCode::KeyJ,
+                KeyCode::KeyK,
+                KeyCode::KeyL,
+            ],
+        };
+        keys.get(lane.index()).copied()
+    }
+
+    /// Texture of notes in the given lane. Arrow lanes show the matching arrow fish, the
+    /// extra lanes of the wider layouts use the hook.
+    pub fn sprite(self, lane: Lane) -> &'static str {
+        let sprites: &[&str] = match self {
+            LaneLayout::Four => &[LEFT_SPRITE, DOWN_SPRITE, UP_SPRITE, RIGHT_SPRITE],
+            LaneLayout::Five => &[LEFT_SPRITE, DOWN_SPRITE, HOOK_SPRITE, UP_SPRITE, RIGHT_SPRITE],
+            LaneLayout::Six => &[
+                LEFT_SPRITE,
+                DOWN_SPRITE,
+                HOOK_SPRITE,
+                HOOK_SPRITE,
+                UP_SPRITE,
+                RIGHT_SPRITE,
+            ],
+        };
+        sprites.get(lane.index()).copied().unwrap_or(HOOK_SPRITE)
+    }
+
+    /// Lane hit by the given key, if the key is bound in this layout.
+    pub fn lane_for_key(self, key: KeyCode) -> Option<Lane> {
+        self.lanes()
+            .iter()
+            .copied()
+            .find(|lane| self.key(*lane) == Some(key))
+    }
+}
@@ -3,6 +3,7 @@ use fyrox::{
     core::{
         algebra::{Vector2, Vector3},
         futures::executor::block_on,
+        log::Log,
         pool::Handle,
         reflect::prelude::*,
         uuid::{uuid, Uuid},
@@ -10,22 +11,85 @@ use fyrox::{
     },
     asset::manager::ResourceManager,
     event::{ElementState, Event, WindowEvent},
-    keyboard::KeyCode,
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
     impl_component_provider,
     resource::texture::Texture,
-    gui::message::UiMessage,
+    gui::{
+        button::ButtonMessage,
+        message::{MessageDirection, UiMessage},
+        UserInterface,
+    },
     plugin::{Plugin, PluginConstructor, PluginContext, PluginRegistrationContext},
     scene::{
+        base::BaseBuilder,
         dim2::{rectangle::Rectangle, rigidbody::RigidBody},
         node::{Node},
+        sound::{Sound, SoundBuffer, SoundBuilder, Status},
         Scene, SceneLoader,
     },
     script::{ScriptContext, ScriptTrait},
 };
-use std::path::Path;
+use std::{path::Path, time::{SystemTime, UNIX_EPOCH}};
+use crate::{
+    audio::{AudioManager, Cue, SoundMap, SOUND_MAP_PATH},
+    autoplay::Autoplay,
+    chart::{Chart, NoteId},
+    chart_editor::{ChartEditor, EditorAction},
+    chart_gen::GeneratorParams,
+    difficulty::RhythmParams,
+    events::{EventBus, GameEvent, Item, Subscription},
+    fish::{FishLog, FishRegistry, CHARTS_DIR, FISH_REGISTRY_PATH},
+    fishdex::Fishdex,
+    hit_feedback::HitFeedback,
+    judge::{HitWindows, Judged},
+    lanes::{Lane, LaneLayout},
+    music::{MusicManager, MusicMap, MUSIC_MAP_PATH},
+    note_pool::NotePool,
+    pause_menu::{PauseAction, PauseMenu},
+    practice::Practice,
+    practice_menu::{PracticeAction, PracticeMenu},
+    replay::{Replay, ReplayPlayer, LAST_REPLAY_PATH},
+    results::{CatchResult, LandedFish, ResultsScreen},
+    scoring::{CatchStats, Outcome, Progress},
+    save::SaveData,
+    session::CatchSession,
+    settings::{Settings, SETTINGS_PATH},
+    settings_menu::{SettingsAction, SettingsMenu},
+    shop::{CaughtFish, Inventory, ShopCatalogue, SHOP_CATALOGUE_PATH},
+    shop_menu::{ShopAction, ShopMenu},
+};
 
+pub mod audio;
+pub mod autoplay;
+pub mod beat_detect;
+pub mod camera;
+pub mod chart;
+pub mod chart_editor;
+pub mod chart_gen;
+pub mod difficulty;
+pub mod events;
+pub mod fish;
+pub mod fishdex;
+pub mod follow_camera;
+pub mod hit_feedback;
+pub mod judge;
+pub mod lanes;
+pub mod music;
+pub mod note_pool;
+pub mod pause_menu;
 pub mod player;
+pub mod practice;
+pub mod practice_menu;
+pub mod replay;
+pub mod results;
 pub mod rhythm;
+pub mod save;
+pub mod scoring;
+pub mod session;
+pub mod settings;
+pub mod settings_menu;
+pub mod shop;
+pub mod shop_menu;
 
 pub struct GameConstructor;
 
@@ -35,6 +99,12 @@ impl PluginConstructor for GameConstructor {
         script_constructors.add::<player::Player>("Player");
         script_constructors.add::<rhythm::RhythmBlock>("Rhythm Block");
         script_constructors.add::<rhythm::ProgressBar>("Progress Bar");
+        script_constructors.add::<shop::Shopkeeper>("Shopkeeper");
+        script_constructors.add::<follow_camera::FollowCamera>("Follow Camera");
+        _context
+            .serialization_context
+            .node_constructors
+            .add::<camera::Camera>();
     }
 
     fn create_instance(&self, scene_path: Option<&str>, context: PluginContext) -> Box<dyn Plugin> {
@@ -44,16 +114,811 @@ impl PluginConstructor for GameConstructor {
 
 pub struct Game {
     scene: Handle<Scene>,
+    pause_menu: PauseMenu,
+    settings: Settings,
+    settings_menu: SettingsMenu,
+    fish: FishRegistry,
+    fish_log: FishLog,
+    fishdex: Fishdex,
+    catalogue: ShopCatalogue,
+    inventory: Inventory,
+    shop_menu: ShopMenu,
+    shop_requested: bool,
+    results: ResultsScreen,
+    hit_feedback: HitFeedback,
+    audio: AudioManager,
+    music: MusicManager,
+    pending_result: Option<CatchResult>,
+    last_result: Option<CatchResult>,
+    lane_layout: LaneLayout,
+    //judges the notes of the catch and keeps its score. notes are keyed by their index in the
+    //chart being played, hand-placed blocks of old scenes get ids past its end
+    session: CatchSession,
+    next_note_id: NoteId,
+    //species id and size of the fish on the line during a catch
+    hooked_fish: Option<(String, f32)>,
+    rhythm_params: RhythmParams,
+    chart: Chart,
+    //where the chart of the current catch is read from and saved to by the editor
+    chart_path: String,
+    //seconds into the current chart, only advances while a catch is being played
+    song_time: f32,
+    catching: bool,
+    in_catching_scene: bool,
+    //hit-line block of every lane and the blocks showing the notes that are in view
+    lane_references: Vec<Handle<Node>>,
+    note_pool: NotePool,
+    chart_editor: ChartEditor,
+    //song played while previewing in the chart editor
+    editor_song: Handle<Node>,
+    modifiers: ModifiersState,
+    autoplay: Autoplay,
+    autoplay_enabled: bool,
+    //autoplayed catches are shown but not recorded
+    autoplay_used: bool,
+    //inputs of the catch being played, saved once it is over
+    recorder: Option<Replay>,
+    //replay being played back instead of the player's inputs
+    replay_player: Option<ReplayPlayer>,
+    practice_menu: PracticeMenu,
+    //set while the current catch is a practice run
+    practice: Option<Practice>,
+    events: EventBus,
+    //whether a menu was open last frame, to announce pausing and resuming
+    was_paused: bool,
+}
+
+//session held while no catch is running, it has no notes to judge
+fn idle_session() -> CatchSession {
+    CatchSession::new(
+        Chart::default(),
+        HitWindows::default(),
+        0.0,
+        Progress::default(),
+    )
 }
 
+/// Scene the player walks around in between catches.
+pub const OVERWORLD_SCENE: &str = "data/scene.rgs";
+/// Scene with the rhythm minigame.
+pub const CATCHING_SCENE: &str = "data/catching.rgs";
+
 impl Game {
     pub fn new(scene_path: Option<&str>, context: PluginContext) -> Self {
         context
             .async_scene_loader
-            .request(scene_path.unwrap_or("data/scene.rgs"));
+            .request(scene_path.unwrap_or(OVERWORLD_SCENE));
+
+        let settings = Settings::load(SETTINGS_PATH);
+        let pause_menu = PauseMenu::new(context.user_interface);
+        let settings_menu = SettingsMenu::new(context.user_interface, &settings);
+        let fish = FishRegistry::load(FISH_REGISTRY_PATH);
+        let fishdex = Fishdex::new(context.user_interface, &fish);
+        let catalogue = ShopCatalogue::load(SHOP_CATALOGUE_PATH);
+        let audio = AudioManager::new(SoundMap::load(SOUND_MAP_PATH));
+        let music = MusicManager::new(MusicMap::load(MUSIC_MAP_PATH), context.scenes);
+        let shop_menu = ShopMenu::new(context.user_interface, &catalogue);
+        let results = ResultsScreen::new(context.user_interface);
+        let hit_feedback = HitFeedback::new(context.user_interface);
+        let chart_editor = ChartEditor::new(context.user_interface);
+        let practice_menu = PracticeMenu::new(context.user_interface);
 
-        Self {
+        let game = Self {
             scene: Handle::NONE,
+            pause_menu,
+            settings,
+            settings_menu,
+            fish,
+            fish_log: FishLog::default(),
+            fishdex,
+            catalogue,
+            inventory: Inventory::default(),
+            shop_menu,
+            shop_requested: false,
+            results,
+            hit_feedback,
+            audio,
+            music,
+            pending_result: None,
+            last_result: None,
+            lane_layout: LaneLayout::default(),
+            session: idle_session(),
+            next_note_id: 0,
+            hooked_fish: None,
+            rhythm_params: RhythmParams::default(),
+            chart: Chart::default(),
+            chart_path: String::new(),
+            song_time: 0.0,
+            catching: false,
+            in_catching_scene: false,
+            lane_references: Vec::new(),
+            note_pool: NotePool::default(),
+            chart_editor,
+            editor_song: Handle::NONE,
+            modifiers: ModifiersState::default(),
+            autoplay: Autoplay::default(),
+            autoplay_enabled: false,
+            autoplay_used: false,
+            recorder: None,
+            replay_player: None,
+            practice_menu,
+            practice: None,
+            events: EventBus::default(),
+            was_paused: false,
+        };
+        game.apply_ui_scale(context.user_interface);
+        game
+    }
+
+    /// The scene being played. Music plays in a scene of its own, see [`music`].
+    pub fn scene(&self) -> Handle<Scene> {
+        self.scene
+    }
+
+    /// Whether gameplay should be frozen, scripts check this before moving anything.
+    pub fn is_paused(&self) -> bool {
+        self.pause_menu.is_open()
+            || self.settings_menu.is_open()
+            || self.fishdex.is_open()
+            || self.shop_menu.is_open()
+            || self.results.is_open()
+            || self.practice_menu.is_open()
+    }
+
+    /// Queues an event for everyone listening, see [`events`].
+    pub fn emit(&mut self, event: GameEvent) {
+        self.events.emit(event);
+    }
+
+    /// Events published this frame, in the order they were emitted. See [`events`] for when
+    /// a batch can be read exactly once.
+    pub fn events(&self) -> &[GameEvent] {
+        self.events.published()
+    }
+
+    /// Starts collecting events for [`Self::read_events`].
+    pub fn subscribe(&mut self) -> Subscription {
+        self.events.subscribe()
+    }
+
+    pub fn unsubscribe(&mut self, subscription: Subscription) {
+        self.events.unsubscribe(subscription);
+    }
+
+    /// Every event published since `subscription` last read, in order.
+    pub fn read_events(&mut self, subscription: Subscription) -> Vec<GameEvent> {
+        self.events.read(subscription)
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Lanes used by the catch currently being played.
+    pub fn lane_layout(&self) -> LaneLayout {
+        self.lane_layout
+    }
+
+    pub fn set_lane_layout(&mut self, layout: LaneLayout) {
+        self.lane_layout = layout;
+    }
+
+    /// Judgements, counts and progress of the catch currently being played.
+    pub fn session(&self) -> &CatchSession {
+        &self.session
+    }
+
+    /// Has a note that is not part of the chart judged, such as a hand-placed block.
+    /// `hit_time` includes the audio offset.
+    pub fn track_note(&mut self, note: NoteId, lane: Lane, hit_time: f32) {
+        self.session.track(note, lane, hit_time);
+    }
+
+    /// Blocks showing the notes of the catch that are in view.
+    pub fn note_pool(&self) -> &NotePool {
+        &self.note_pool
+    }
+
+    /// Plays a sound effect on the next update.
+    pub fn play_sound(&mut self, cue: Cue) {
+        self.audio.play(cue);
+    }
+
+    /// Hands out an id for a note that is not part of the chart, such as a hand-placed block.
+    pub fn allocate_note_id(&mut self) -> NoteId {
+        let id = self.next_note_id;
+        self.next_note_id += 1;
+        id
+    }
+
+    /// Speed, density and hit windows of the catch currently being played.
+    pub fn rhythm_params(&self) -> RhythmParams {
+        self.rhythm_params
+    }
+
+    /// Chart of the catch currently being played.
+    pub fn chart(&self) -> &Chart {
+        &self.chart
+    }
+
+    /// Seconds since the current chart started, frozen while paused. Follows the cursor while
+    /// the chart editor is open.
+    pub fn song_time(&self) -> f32 {
+        self.song_time
+    }
+
+    /// Seconds added to the ideal moment of every note, for audio that arrives late. Replays
+    /// use the offset they were recorded with.
+    pub fn audio_offset(&self) -> f32 {
+        let ms = match &self.replay_player {
+            Some(player) => player.replay().audio_offset_ms,
+            None => self.settings.audio_offset_ms,
+        };
+        ms as f32 / 1000.0
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.chart_editor.is_open()
+    }
+
+    /// Whether running out of progress is ignored, as in a practice run with no-fail on.
+    pub fn no_fail(&self) -> bool {
+        self.practice
+            .as_ref()
+            .is_some_and(|practice| practice.options().no_fail)
+    }
+
+    /// Whether note blocks should be judged. The chart editor only shows notes, unless it is
+    /// previewing the chart.
+    pub fn notes_judged(&self) -> bool {
+        !self.is_editing() || self.chart_editor.is_playing()
+    }
+
+    //picks the fish on the line, sets the difficulty of the minigame from it and fills the
+    //scene with the notes of its chart. replays bring their own fish and difficulty, practice
+    //runs keep the fish that was already on the line
+    fn begin_catch(&mut self, scene: &mut Scene, resource_manager: &ResourceManager) {
+        let replayed = self.replay_player.as_ref().map(|player| player.replay());
+        let difficulty = replayed.map_or(self.settings.difficulty, |replay| replay.difficulty);
+        let hooked = match (replayed, &self.practice, &self.hooked_fish) {
+            (Some(replay), _, _) => self
+                .fish
+                .get(&replay.species)
+                .map(|species| (species, replay.size)),
+            (None, Some(_), Some((id, size))) => self.fish.get(id).map(|species| (species, *size)),
+            _ => self.fish.roll(&mut fyrox::core::rand::thread_rng()),
+        };
+        let (rating, chart, seed_key) = match hooked {
+            Some((species, size)) => {
+                self.hooked_fish = Some((species.id.clone(), size));
+                self.chart_path = species.chart_path();
+                //a missing chart is only worth mentioning if the species asked for one
+                let chart = if species.chart.is_some() || Path::new(&self.chart_path).exists() {
+                    Chart::load(&self.chart_path)
+                        .map_err(|e| {
+                            Log::err(format!("Unable to load chart {}: {e}", self.chart_path))
+                        })
+                        .ok()
+                } else {
+                    None
+                };
+                (species.difficulty, chart, species.id.clone())
+            }
+            None => {
+                Log::err("Started a catch, but the fish registry is empty.");
+                self.hooked_fish = None;
+                self.chart_path = format!("{CHARTS_DIR}/untitled.ron");
+                (1.0, None, String::new())
+            }
+        };
+        self.rhythm_params = difficulty.params(rating);
+
+        //fish without a chart of their own always get the same generated one
+        self.chart = chart.unwrap_or_else(|| {
+            chart_gen::generate(&GeneratorParams {
+                seed: chart_gen::seed_from_str(&format!("{seed_key}:{difficulty:?}")),
+                bpm: 90.0 + 30.0 * rating,
+                layout: difficulty.lane_layout(rating),
+                density: self.rhythm_params.density,
+                ..Default::default()
+            })
+        });
+        self.set_lane_layout(self.chart.layout);
+        if let Some(practice) = self.practice.as_mut() {
+            *practice = Practice::new(practice.options(), &self.chart);
+        }
+
+        self.song_time = self.practice.as_ref().map_or(0.0, Practice::start_time);
+        self.catching = true;
+        let played = self.practice.as_ref().map_or(&self.chart, Practice::chart);
+        self.autoplay.seek(played, self.song_time);
+        self.autoplay_used = self.autoplay_enabled;
+
+        let checksum = replay::chart_checksum(&self.chart, &self.rhythm_params.windows);
+        self.recorder = match &self.replay_player {
+            Some(player) => {
+                if player.replay().chart_checksum != checksum {
+                    Log::err(
+                        "The chart or its hit windows changed since the replay was recorded, it \
+                        will play out differently.",
+                    );
+                }
+                None
+            }
+            None if self.practice.is_some() => None,
+            None => Some(Replay {
+                species: self
+                    .hooked_fish
+                    .as_ref()
+                    .map(|(id, _)| id.clone())
+                    .unwrap_or_default(),
+                size: self.hooked_fish.as_ref().map_or(0.0, |(_, size)| *size),
+                difficulty,
+                audio_offset_ms: self.settings.audio_offset_ms,
+                chart_checksum: checksum,
+                events: Vec::new(),
+            }),
+        };
+
+        let mut progress = Progress::new(rhythm::progress_goal(scene));
+        progress.set_no_fail(self.no_fail());
+        self.session = CatchSession::new(
+            Chart::default(),
+            self.rhythm_params.windows,
+            self.audio_offset(),
+            progress,
+        );
+
+        self.lane_references = rhythm::setup_lanes(scene, self.chart.layout, resource_manager);
+        if !self.lane_references.is_empty() {
+            self.note_pool.fill(scene, resource_manager);
+        }
+        self.reset_notes(scene);
+
+        self.events.emit(GameEvent::CatchStarted {
+            species: self.hooked_fish.as_ref().map(|(id, _)| id.clone()),
+            practice: self.practice.is_some(),
+        });
+    }
+
+    //starts the chart being played or edited over from the current song time, the pool shows
+    //its notes from the next update on
+    fn reset_notes(&mut self, scene: &mut Scene) {
+        self.note_pool.reset(scene);
+
+        //the session knows every note up front, whether or not it is in view. scenes without
+        //lanes keep their hand-placed blocks and judge nothing else
+        let chart = if self.lane_references.is_empty() {
+            Chart::default()
+        } else {
+            self.active_chart().clone()
+        };
+        self.next_note_id = chart.notes.len();
+        self.session.restart(chart, self.song_time);
+    }
+
+    //shows the notes coming into view on pooled blocks and takes back the ones that went past
+    fn update_note_pool(&mut self, context: &mut PluginContext) {
+        let Some(scene) = context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
+        let chart = if self.is_editing() {
+            self.chart_editor.chart()
+        } else if let Some(practice) = &self.practice {
+            practice.chart()
+        } else {
+            &self.chart
+        };
+        let speed = self.rhythm_params.scroll_speed * self.settings.scroll_speed;
+        self.note_pool.update(
+            scene,
+            chart,
+            &self.lane_references,
+            self.song_time,
+            speed,
+            context.resource_manager,
+        );
+    }
+
+    /// Turns autoplay on or off for the current and following catches.
+    pub fn set_autoplay(&mut self, enabled: bool) {
+        self.autoplay_enabled = enabled;
+        if enabled {
+            let mut autoplay = std::mem::take(&mut self.autoplay);
+            autoplay.seek(self.active_chart(), self.song_time);
+            self.autoplay = autoplay;
+            self.autoplay_used |= self.catching;
+        }
+        Log::info(format!("Autoplay {}", if enabled { "on" } else { "off" }));
+    }
+
+    //the chart being played: the one in the editor, the practised section or the whole catch
+    fn active_chart(&self) -> &Chart {
+        if self.is_editing() {
+            self.chart_editor.chart()
+        } else if let Some(practice) = &self.practice {
+            practice.chart()
+        } else {
+            &self.chart
+        }
+    }
+
+    //presses the notes autoplay has reached at their ideal moment, audio offset included
+    fn play_autoplay(&mut self) {
+        let audio_offset = self.audio_offset();
+        let mut autoplay = std::mem::take(&mut self.autoplay);
+        let due = autoplay
+            .due(self.active_chart(), self.song_time - audio_offset)
+            .to_vec();
+        self.autoplay = autoplay;
+        let mut judged = Vec::new();
+        for note in due {
+            judged.extend(self.session.press(note.lane, note.time + audio_offset));
+        }
+        self.announce(judged);
+    }
+
+    /// Feeds a key going down or up to the lanes of the catch. Returns false for keys that
+    /// are not bound to a lane.
+    pub fn lane_key(&mut self, key: KeyCode, pressed: bool) -> bool {
+        match self.lane_layout.lane_for_key(key) {
+            Some(lane) => {
+                self.lane_input(lane, pressed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //a lane key going down or up during a catch, ignored while autoplay or a replay plays
+    fn lane_input(&mut self, lane: Lane, pressed: bool) {
+        //lane keys overlap the movement keys, outside of a catch they only walk
+        if !self.catching || self.autoplay_enabled || self.replay_player.is_some() {
+            return;
+        }
+        if pressed {
+            let judged = self.session.press(lane, self.song_time);
+            self.announce(judged);
+            self.audio.play(Cue::ReelClick);
+        } else {
+            //letting go ends a hold
+            let judged = self.session.release(lane, self.song_time);
+            self.announce(judged);
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(self.song_time, lane, pressed);
+        }
+    }
+
+    //feeds the replay inputs the song has reached back into the session, at the song time
+    //they were recorded at
+    fn play_replay(&mut self) {
+        let Some(player) = self.replay_player.as_mut() else {
+            return;
+        };
+        let judged: Vec<_> = player
+            .due(self.song_time)
+            .iter()
+            .flat_map(|event| self.session.input(event))
+            .collect();
+        self.announce(judged);
+    }
+
+    /// Plays back the replay stored at `path` in a fresh catch.
+    pub fn start_replay(&mut self, path: &str, context: &mut PluginContext) {
+        match Replay::load(path) {
+            Ok(replay) => {
+                self.replay_player = Some(ReplayPlayer::new(replay));
+                context.async_scene_loader.request(CATCHING_SCENE);
+            }
+            Err(e) => Log::err(format!("Unable to load replay {path}: {e}")),
+        }
+    }
+
+    //hands the latest judgements to the practice run and loops its section once it is over
+    fn update_practice(&mut self, context: &mut PluginContext) {
+        let audio_offset = self.audio_offset();
+        let playing = self.catching && !self.is_paused();
+        let Some(practice) = self.practice.as_mut() else {
+            return;
+        };
+        for event in self.events.published() {
+            if let GameEvent::Judged {
+                judgement,
+                hit_time,
+                ..
+            } = event
+            {
+                practice.record(hit_time - audio_offset, *judgement);
+            }
+        }
+        if !playing {
+            return;
+        }
+
+        let late_window = self.rhythm_params.windows.ok + audio_offset;
+        let Some(start) = practice.loop_restart(self.song_time, late_window) else {
+            return;
+        };
+        self.song_time = start;
+        if let Some(scene) = context.scenes.try_get_mut(self.scene) {
+            self.reset_notes(scene);
+        }
+        if let Some(practice) = &self.practice {
+            self.autoplay.seek(practice.chart(), start);
+        }
+    }
+
+    fn handle_practice_action(
+        &mut self,
+        action: Option<PracticeAction>,
+        context: &mut PluginContext,
+    ) {
+        match action {
+            //the catch starts over on the same fish
+            Some(PracticeAction::Start(options)) => {
+                self.practice = Some(Practice::new(options, &self.chart));
+                self.practice_menu.close(context.user_interface);
+                self.pause_menu.set_open(false, context.user_interface);
+                context.async_scene_loader.request(CATCHING_SCENE);
+            }
+            Some(PracticeAction::End) => {
+                self.practice_menu.close(context.user_interface);
+                self.pause_menu.set_open(false, context.user_interface);
+                if let Some(stats) = self.practice.as_ref().map(Practice::total) {
+                    self.finish_catch(false, stats);
+                }
+            }
+            Some(PracticeAction::Back) => self.practice_menu.close(context.user_interface),
+            None => (),
+        }
+    }
+
+    //queues judgements of the session for everyone listening
+    fn announce(&mut self, judged: Vec<Judged<NoteId>>) {
+        for judged in judged {
+            self.events.emit(GameEvent::Judged {
+                note: judged.note,
+                lane: judged.lane,
+                judgement: judged.judgement,
+                hit_time: judged.hit_time,
+            });
+        }
+    }
+
+    //announces pause changes, then publishes everything emitted since the last frame
+    fn publish_events(&mut self) {
+        let paused = self.is_paused();
+        if paused != self.was_paused {
+            self.was_paused = paused;
+            self.events.emit(GameEvent::PauseChanged { paused });
+        }
+        self.events.publish();
+    }
+
+    fn open_chart_editor(&mut self, context: &mut PluginContext) {
+        self.catching = false;
+        self.chart_editor.open(
+            self.chart.clone(),
+            self.chart_path.clone(),
+            context.user_interface,
+        );
+
+        let scene = context.scenes.try_get_mut(self.scene);
+        if let (Some(song), Some(scene)) = (&self.chart.song, scene) {
+            let buffer = context.resource_manager.request::<SoundBuffer>(song);
+            self.editor_song = SoundBuilder::new(BaseBuilder::new())
+                .with_buffer(Some(buffer))
+                .with_status(Status::Stopped)
+                .with_spatial_blend_factor(0.0)
+                .with_gain(self.settings.music_gain())
+                .build(&mut scene.graph);
+        }
+    }
+
+    //the catch starts over with whatever chart was saved last
+    fn close_chart_editor(&mut self, context: &mut PluginContext) {
+        self.chart_editor.close(context.user_interface);
+        self.editor_song = Handle::NONE;
+        context.async_scene_loader.request(CATCHING_SCENE);
+    }
+
+    fn handle_editor_action(
+        &mut self,
+        action: Option<EditorAction>,
+        context: &mut PluginContext,
+    ) {
+        match action {
+            Some(EditorAction::Save) => {
+                let path = self.chart_editor.path();
+                match self.chart_editor.chart().save(path) {
+                    Ok(()) => Log::info(format!("Chart saved to {path}")),
+                    Err(e) => Log::err(format!("Unable to save chart {path}: {e}")),
+                }
+            }
+            Some(EditorAction::Close) => self.close_chart_editor(context),
+            None => (),
+        }
+    }
+
+    //moves the editor cursor, plays its notes while previewing and keeps the scene in sync
+    fn update_chart_editor(&mut self, context: &mut PluginContext) {
+        self.chart_editor.update(context.dt, context.user_interface);
+        self.song_time = self.chart_editor.time();
+
+        let Some(scene) = context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
+        //the session only moves forward, so moving the cursor starts the notes over as well
+        let dirty = self.chart_editor.take_dirty();
+        let seeked = self.chart_editor.take_seeked();
+        if seeked || dirty {
+            self.reset_notes(scene);
+            //previews are played by autoplay, starting from the cursor
+            self.autoplay.seek(self.chart_editor.chart(), self.song_time);
+        }
+        if self.chart_editor.is_playing() {
+            self.play_autoplay();
+            let judged = self.session.advance(self.song_time);
+            self.announce(judged);
+        }
+
+        if let Some(sound) = scene
+            .graph
+            .try_get_mut(self.editor_song)
+            .and_then(|node| node.cast_mut::<Sound>())
+        {
+            if seeked {
+                sound.set_playback_time(self.song_time);
+            }
+            match (self.chart_editor.is_playing(), sound.status()) {
+                (true, Status::Playing) => (),
+                (true, _) => sound.play(),
+                (false, Status::Playing) => sound.pause(),
+                (false, _) => (),
+            }
+        }
+    }
+
+    fn apply_ui_scale(&self, ui: &UserInterface) {
+        let roots = [
+            self.pause_menu.root(),
+            self.settings_menu.root(),
+            self.fishdex.root(),
+            self.shop_menu.root(),
+            self.results.root(),
+            self.hit_feedback.root(),
+            self.chart_editor.root(),
+            self.practice_menu.root(),
+        ];
+        self.settings.apply_ui_scale(ui, &roots);
+    }
+
+    //called once the catching minigame is decided, the results screen opens on the next frame
+    fn finish_catch(&mut self, won: bool, stats: CatchStats) {
+        self.catching = false;
+        let grade = stats.grade();
+        let practice = self.practice.take();
+        let mut result = CatchResult {
+            won,
+            fish: None,
+            stats,
+            grade,
+            money_earned: 0,
+            recorded: !self.autoplay_used && self.replay_player.is_none() && practice.is_none(),
+            sections: practice
+                .map(|practice| practice.sections().to_vec())
+                .unwrap_or_default(),
+        };
+        self.replay_player = None;
+        if let Some(replay) = self.recorder.take().filter(|_| result.recorded) {
+            if let Err(e) = replay.save(LAST_REPLAY_PATH) {
+                Log::err(format!("Unable to save replay: {e}"));
+            }
+        }
+
+        let hooked = self.hooked_fish.take();
+        let species = hooked
+            .as_ref()
+            .and_then(|(id, size)| Some((self.fish.get(id)?, *size)));
+        //practice runs never land the fish, even when the bar fills up
+        if won && result.sections.is_empty() {
+            if let Some((species, size)) = species {
+                //autoplayed and replayed catches are shown, but nothing is kept
+                let mut new_record = false;
+                if result.recorded {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or_default();
+                    new_record = self.fish_log.record(&species.id, size, Some(grade), now);
+                    self.inventory.fish.push(CaughtFish {
+                        species: species.id.clone(),
+                        size,
+                        grade: Some(grade),
+                    });
+                    self.events.emit(GameEvent::ItemGained(Item::Fish {
+                        species: species.id.clone(),
+                        size,
+                    }));
+
+                    result.money_earned = shop::catch_bonus(grade);
+                    self.inventory.money += result.money_earned;
+                    if result.money_earned > 0 {
+                        self.events
+                            .emit(GameEvent::ItemGained(Item::Money(result.money_earned)));
+                    }
+                }
+                result.fish = Some(LandedFish {
+                    name: species.name.clone(),
+                    sprite: species.sprite.clone(),
+                    size,
+                    weight_grams: fish::weight_grams(size),
+                    value: shop::fish_price(species, size, Some(grade)),
+                    new_record,
+                });
+            } else {
+                Log::err("Caught a fish, but nothing was on the line.");
+            }
+        }
+
+        self.events.emit(GameEvent::CatchEnded {
+            won,
+            stats: result.stats,
+        });
+        self.pending_result = Some(result);
+    }
+
+    /// Result of the last finished catch, once the results screen has shown it.
+    pub fn last_result(&self) -> Option<&CatchResult> {
+        self.last_result.as_ref()
+    }
+
+    /// Called by the shopkeeper when the player interacts with it, the shop opens next frame.
+    pub fn request_shop(&mut self) {
+        self.shop_requested = true;
+    }
+
+    fn save_game(&self, context: &PluginContext) {
+        let mut data = SaveData {
+            fish_log: self.fish_log.clone(),
+            inventory: self.inventory.clone(),
+            ..Default::default()
+        };
+        if let Some(scene) = context.scenes.try_get(self.scene) {
+            let player = player::find_player(&scene.graph);
+            if player.is_some() {
+                let position = scene.graph[player].global_position();
+                data.player.position = (position.x, position.y);
+            }
+        }
+
+        match save::save_to_file(save::SAVE_PATH, &data) {
+            Ok(()) => Log::info(format!("Game saved to {}", save::SAVE_PATH)),
+            Err(e) => Log::err(format!("Unable to save the game: {e}")),
+        }
+    }
+
+    fn load_game(&mut self, context: &mut PluginContext) {
+        let data = match save::load_from_file(save::SAVE_PATH) {
+            Ok(data) => data,
+            Err(e) => {
+                Log::err(format!("Unable to load the game: {e}"));
+                return;
+            }
+        };
+
+        self.fish_log = data.fish_log;
+        self.inventory = data.inventory;
+
+        if let Some(scene) = context.scenes.try_get_mut(self.scene) {
+            let player = player::find_player(&scene.graph);
+            if player.is_some() {
+                let transform = scene.graph[player].local_transform_mut();
+                let z = transform.position().z;
+                let (x, y) = data.player.position;
+                transform.set_position(Vector3::new(x, y, z));
+            }
         }
     }
 }
@@ -63,30 +928,244 @@ impl Plugin for Game {
         // Do a cleanup here.
     }
 
-    fn update(&mut self, _context: &mut PluginContext) {
-        // Add your global update code here.
+    fn update(&mut self, context: &mut PluginContext) {
+        if self.is_editing() {
+            self.update_chart_editor(context);
+        } else if self.catching && !self.is_paused() {
+            let speed = self
+                .practice
+                .as_ref()
+                .map_or(1.0, |practice| practice.options().speed);
+            self.song_time += context.dt * speed;
+            self.play_replay();
+            if self.autoplay_enabled {
+                self.play_autoplay();
+            }
+            //only once every input up to now went in, so late presses are not missed early
+            let judged = self.session.advance(self.song_time);
+            self.announce(judged);
+
+            match self.session.outcome() {
+                Some(Outcome::Won) => self.finish_catch(true, *self.session.stats()),
+                Some(Outcome::Lost) => self.finish_catch(false, *self.session.stats()),
+                None => (),
+            }
+        }
+        if self.in_catching_scene {
+            self.update_note_pool(context);
+        }
+        //the game's own cameras are drawn through engine cameras that copy their settings
+        if let Some(scene) = context.scenes.try_get_mut(self.scene) {
+            camera::sync_render_cameras(&mut scene.graph);
+        }
+
+        self.publish_events();
+        self.hit_feedback.update(
+            self.events.published(),
+            self.session.stats().combo,
+            context.scenes.try_get_mut(self.scene),
+            &self.lane_references,
+            context.dt,
+            context.user_interface,
+        );
+        self.audio.update(
+            self.events.published(),
+            context.scenes.try_get_mut(self.scene),
+            &self.settings,
+            context.resource_manager,
+        );
+        //the chart editor plays the chart's own song
+        self.music.update(
+            context.dt,
+            self.is_paused() || self.is_editing(),
+            &self.settings,
+            context.scenes,
+        );
+        self.update_practice(context);
+
+        if let Some(result) = self.pending_result.take() {
+            self.results
+                .open(&result, context.resource_manager, context.user_interface);
+            self.last_result = Some(result);
+        }
+
+        if std::mem::take(&mut self.shop_requested) && !self.is_paused() {
+            self.shop_menu
+                .open(&self.inventory, &self.fish, context.user_interface);
+        }
+    }
+
+    fn on_graphics_context_initialized(&mut self, context: PluginContext) {
+        self.settings.apply_window(context.graphics_context);
     }
 
     fn on_os_event(
         &mut self,
-        _event: &Event<()>,
-        _context: PluginContext,
+        event: &Event<()>,
+        mut context: PluginContext,
     ) {
+        if let Event::WindowEvent { event: WindowEvent::ModifiersChanged(modifiers), .. } = event {
+            self.modifiers = modifiers.state();
+        }
+
+        //escape opens and closes the pause menu
+        if let Event::WindowEvent { event: WindowEvent::KeyboardInput { event, .. }, .. } = event {
+            if event.state == ElementState::Pressed
+                && !event.repeat
+                && event.physical_key == fyrox::keyboard::PhysicalKey::Code(KeyCode::Escape)
+            {
+                self.audio.play(Cue::UiBack);
+                //backs out of the settings screen or the fishdex first
+                if self.is_editing() {
+                    self.close_chart_editor(&mut context);
+                } else if self.practice_menu.is_open() {
+                    self.practice_menu.close(context.user_interface);
+                } else if self.settings_menu.is_open() {
+                    self.settings_menu.close(context.user_interface);
+                } else if self.fishdex.is_open() {
+                    self.fishdex.close(context.user_interface);
+                } else if self.shop_menu.is_open() {
+                    self.shop_menu.close(context.user_interface);
+                } else {
+                    self.pause_menu.toggle(context.user_interface);
+                }
+            }
 
+            //lane keys hit whatever notes are in reach, several lanes at once make a chord. the
+            //chart editor takes over the keyboard while it is open
+            if !event.repeat && !self.is_paused() {
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    let pressed = event.state == ElementState::Pressed;
+                    if self.is_editing() {
+                        if pressed {
+                            let ctrl = self.modifiers.control_key();
+                            let action =
+                                self.chart_editor.handle_key(key, ctrl, context.user_interface);
+                            self.handle_editor_action(action, &mut context);
+                        }
+                    } else if pressed
+                        && key == KeyCode::F2
+                        && self.in_catching_scene
+                        && self.practice.is_none()
+                    {
+                        self.open_chart_editor(&mut context);
+                    } else if pressed && key == KeyCode::F3 {
+                        self.set_autoplay(!self.autoplay_enabled);
+                    } else if pressed && key == KeyCode::F5 && !self.in_catching_scene {
+                        self.start_replay(LAST_REPLAY_PATH, &mut context);
+                    } else {
+                        self.lane_key(key, pressed);
+                    }
+                }
+            }
+        }
     }
 
     fn on_ui_message(
         &mut self,
-        _context: &mut PluginContext,
-        _message: &UiMessage,
+        context: &mut PluginContext,
+        message: &UiMessage,
     ) {
-        // Handle UI events here.
+        if let Some(ButtonMessage::Click) = message.data() {
+            if message.direction() == MessageDirection::FromWidget {
+                self.audio.play(Cue::UiClick);
+            }
+        }
+
+        match self.pause_menu.handle_ui_message(message) {
+            Some(PauseAction::Resume) => self.pause_menu.set_open(false, context.user_interface),
+            Some(PauseAction::Fishdex) => self.fishdex.open(
+                &self.fish,
+                &self.fish_log,
+                context.resource_manager,
+                context.user_interface,
+            ),
+            Some(PauseAction::Settings) => {
+                self.settings_menu.open(&self.settings, context.user_interface)
+            }
+            Some(PauseAction::Practice) => {
+                if self.catching {
+                    let options = self
+                        .practice
+                        .as_ref()
+                        .map(Practice::options)
+                        .unwrap_or_default();
+                    self.practice_menu.open(
+                        options,
+                        practice::section_count(&self.chart),
+                        self.practice.is_some(),
+                        context.user_interface,
+                    );
+                }
+            }
+            Some(PauseAction::Save) => self.save_game(context),
+            Some(PauseAction::Load) => {
+                self.load_game(context);
+                self.pause_menu.set_open(false, context.user_interface);
+            }
+            Some(PauseAction::Quit) => {
+                if let Some(window_target) = context.window_target {
+                    window_target.exit();
+                }
+            }
+            None => (),
+        }
+
+        match self.settings_menu.handle_ui_message(message, context.user_interface) {
+            Some(SettingsAction::Apply(settings)) => {
+                self.settings = settings;
+                self.settings.save(SETTINGS_PATH);
+                self.settings.apply_window(context.graphics_context);
+                self.apply_ui_scale(context.user_interface);
+                self.settings_menu.close(context.user_interface);
+            }
+            Some(SettingsAction::Back) => self.settings_menu.close(context.user_interface),
+            None => (),
+        }
+
+        let action = self
+            .practice_menu
+            .handle_ui_message(message, context.user_interface);
+        self.handle_practice_action(action, context);
+
+        if self.fishdex.handle_ui_message(message) {
+            self.fishdex.close(context.user_interface);
+        }
+
+        let action = self
+            .chart_editor
+            .handle_ui_message(message, context.user_interface);
+        self.handle_editor_action(action, context);
+
+        if self.results.handle_ui_message(message) {
+            self.results.close(context.user_interface);
+            context.async_scene_loader.request(OVERWORLD_SCENE);
+        }
+
+        match self.shop_menu.handle_ui_message(
+            message,
+            &mut self.inventory,
+            &self.catalogue,
+            &self.fish,
+            context.user_interface,
+        ) {
+            Some(ShopAction::Bought(item)) => {
+                self.events.emit(GameEvent::ItemGained(Item::ShopItem(item)))
+            }
+            Some(ShopAction::Sold(price)) => {
+                self.events.emit(GameEvent::ItemGained(Item::Money(price)))
+            }
+            Some(ShopAction::Close) => self.shop_menu.close(context.user_interface),
+            None => (),
+        }
     }
     
     fn on_scene_begin_loading(&mut self, path: &Path, ctx: &mut PluginContext) {
         if self.scene.is_some() {
             ctx.scenes.remove(self.scene);
         }
+        //the old track fades out while the scene loads
+        self.music.play_for_scene(path, ctx.scenes, ctx.resource_manager);
     }
 
     fn on_scene_loaded(
@@ -97,5 +1176,28 @@ impl Plugin for Game {
         context: &mut PluginContext,
     ) {    
         self.scene = scene;
+        self.events.emit(GameEvent::SceneChanged {
+            path: path.to_path_buf(),
+        });
+        self.catching = false;
+        //notes left over from the last catch are not judged anywhere else
+        self.session = idle_session();
+        self.in_catching_scene = path == Path::new(CATCHING_SCENE);
+        self.lane_references.clear();
+        self.note_pool.forget();
+        self.hit_feedback.reset(context.user_interface);
+        if !self.in_catching_scene {
+            self.practice = None;
+        }
+        //replays are watched as they were played, so they cannot be practised
+        self.pause_menu.set_practice_available(
+            self.in_catching_scene && self.replay_player.is_none(),
+            context.user_interface,
+        );
+        if self.in_catching_scene {
+            if let Some(scene) = context.scenes.try_get_mut(scene) {
+                self.begin_catch(scene, context.resource_manager);
+            }
+        }
     }
 }
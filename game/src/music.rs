@@ -0,0 +1,198 @@
+//! Background music.
+//!
+//! Every scene can have a track, listed in a data file by scene path. Music plays in a scene
+//! of its own that outlives the game's scenes, so the old track can fade out while the next
+//! scene loads and the new one fades in over it. Tracks loop, and the music is turned down
+//! while a menu is open.
+use crate::{audio::SoundCategory, settings::Settings};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{log::Log, pool::Handle},
+    scene::{
+        base::BaseBuilder,
+        node::Node,
+        sound::{Sound, SoundBuffer, SoundBuilder, Status},
+        Scene, SceneContainer,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Where the track of every scene is listed, relative to the working directory.
+pub const MUSIC_MAP_PATH: &str = "data/music.ron";
+
+/// Seconds the music takes to duck or come back when a menu opens or closes.
+const DUCK_TIME: f32 = 0.25;
+
+fn default_fade_time() -> f32 {
+    1.5
+}
+
+fn default_duck_gain() -> f32 {
+    0.35
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MusicMap {
+    /// Track of each scene, by scene path. Scenes missing from the map are silent.
+    #[serde(default)]
+    pub scenes: HashMap<String, String>,
+    /// Seconds a crossfade takes.
+    #[serde(default = "default_fade_time")]
+    pub fade_time: f32,
+    /// Share of the volume left while a menu is open.
+    #[serde(default = "default_duck_gain")]
+    pub duck_gain: f32,
+}
+
+impl Default for MusicMap {
+    fn default() -> Self {
+        Self {
+            scenes: HashMap::new(),
+            fade_time: default_fade_time(),
+            duck_gain: default_duck_gain(),
+        }
+    }
+}
+
+impl MusicMap {
+    /// Reads the music map, falling back to silence if it is missing or broken.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|e| e.to_string()));
+        match result {
+            Ok(map) => map,
+            Err(e) => {
+                Log::err(format!("Unable to load music map {}: {e}", path.display()));
+                Self::default()
+            }
+        }
+    }
+
+    /// Track of the scene at `scene_path`, if it has one.
+    pub fn track(&self, scene_path: &Path) -> Option<&str> {
+        self.scenes
+            .iter()
+            .find(|(scene, _)| Path::new(scene) == scene_path)
+            .map(|(_, track)| track.as_str())
+    }
+}
+
+struct Track {
+    path: String,
+    node: Handle<Node>,
+    //0.0 is silent and 1.0 full volume
+    fade: f32,
+    fading_out: bool,
+}
+
+pub struct MusicManager {
+    map: MusicMap,
+    //scene the music plays in, never unloaded
+    scene: Handle<Scene>,
+    //the track being faded in or played last, the others are fading out
+    tracks: Vec<Track>,
+    //volume left by ducking, eased towards its target
+    duck: f32,
+}
+
+impl MusicManager {
+    pub fn new(map: MusicMap, scenes: &mut SceneContainer) -> Self {
+        Self {
+            map,
+            scene: scenes.add(Scene::new()),
+            tracks: Vec::new(),
+            duck: 1.0,
+        }
+    }
+
+    /// Crossfades to the track of the scene at `scene_path`, or fades out if it has none.
+    pub fn play_for_scene(
+        &mut self,
+        scene_path: &Path,
+        scenes: &mut SceneContainer,
+        resource_manager: &ResourceManager,
+    ) {
+        let track = self.map.track(scene_path).map(str::to_string);
+        self.play(track, scenes, resource_manager);
+    }
+
+    fn play(
+        &mut self,
+        track: Option<String>,
+        scenes: &mut SceneContainer,
+        resource_manager: &ResourceManager,
+    ) {
+        //the same track keeps playing across scenes that share it
+        for playing in self.tracks.iter_mut() {
+            playing.fading_out = Some(&playing.path) != track.as_ref();
+        }
+        let Some(path) = track else {
+            return;
+        };
+        if self.tracks.iter().any(|playing| playing.path == path) {
+            return;
+        }
+        let Some(scene) = scenes.try_get_mut(self.scene) else {
+            return;
+        };
+
+        let buffer = resource_manager.request::<SoundBuffer>(&path);
+        let node = SoundBuilder::new(BaseBuilder::new())
+            .with_buffer(Some(buffer))
+            .with_status(Status::Playing)
+            .with_looping(true)
+            .with_spatial_blend_factor(0.0)
+            .with_gain(0.0)
+            .build(&mut scene.graph);
+        self.tracks.push(Track {
+            path,
+            node,
+            fade: 0.0,
+            fading_out: false,
+        });
+    }
+
+    /// Moves the fades along and applies the music volume. `ducked` turns the music down,
+    /// for when a menu is open.
+    pub fn update(
+        &mut self,
+        dt: f32,
+        ducked: bool,
+        settings: &Settings,
+        scenes: &mut SceneContainer,
+    ) {
+        let Some(scene) = scenes.try_get_mut(self.scene) else {
+            return;
+        };
+
+        let duck_target = if ducked { self.map.duck_gain } else { 1.0 };
+        let duck_step = dt * (1.0 - self.map.duck_gain).abs() / DUCK_TIME;
+        self.duck += (duck_target - self.duck).clamp(-duck_step, duck_step);
+
+        let fade_step = dt / self.map.fade_time.max(f32::EPSILON);
+        let volume = self.duck * SoundCategory::Music.gain(settings);
+        for track in self.tracks.iter_mut() {
+            let target = if track.fading_out { 0.0 } else { 1.0 };
+            track.fade += (target - track.fade).clamp(-fade_step, fade_step);
+            if let Some(sound) = scene
+                .graph
+                .try_get_mut(track.node)
+                .and_then(|node| node.cast_mut::<Sound>())
+            {
+                sound.set_gain(track.fade * volume);
+            }
+        }
+
+        //tracks that faded out are done with
+        self.tracks.retain(|track| {
+            let done = track.fading_out && track.fade <= 0.0;
+            if done && scene.graph.is_valid_handle(track.node) {
+                scene.graph.remove_node(track.node);
+            }
+            !done
+        });
+    }
+}
@@ -0,0 +1,153 @@
+//! Recycled scene nodes for the notes of a chart.
+//!
+//! A long chart has hundreds of notes, but only the few near the hit line are ever on screen.
+//! The pool creates a handful of note blocks up front, hands them to notes as they come into
+//! view and takes them back once the notes have scrolled past, so the scene graph stays the
+//! same size however long the song is.
+use crate::{
+    chart::{Chart, NoteId},
+    rhythm::{self, RhythmBlock},
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{algebra::Vector3, pool::Handle},
+    resource::texture::Texture,
+    scene::{dim2::rectangle::Rectangle, node::Node, Scene},
+};
+
+/// World units above the hit line at which notes come into view. The catching camera shows
+/// a little less than that.
+const VIEW_AHEAD: f32 = 12.0;
+/// World units below the hit line past which notes have left the screen.
+const VIEW_BEHIND: f32 = 4.0;
+/// Blocks created up front, enough for the densest stretch of a generated chart.
+const PREALLOCATED: usize = 48;
+
+#[derive(Debug, Default)]
+pub struct NotePool {
+    free: Vec<Handle<Node>>,
+    //notes on screen and the block showing each of them
+    active: Vec<(NoteId, Handle<Node>)>,
+    //first note of the chart that has not come into view yet
+    next: usize,
+    //song time of the last update
+    time: f32,
+}
+
+impl NotePool {
+    /// Creates the blocks up front, forgetting any blocks of a previous scene.
+    pub fn fill(&mut self, scene: &mut Scene, resource_manager: &ResourceManager) {
+        *self = Self::default();
+        self.free = (0..PREALLOCATED)
+            .map(|_| rhythm::spawn_note_block(scene, resource_manager))
+            .collect();
+    }
+
+    /// Drops every block without touching the scene, for when the scene is gone.
+    pub fn forget(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Takes back every block, the next update shows the chart from the start again.
+    pub fn reset(&mut self, scene: &mut Scene) {
+        for (_, block) in std::mem::take(&mut self.active) {
+            self.release(scene, block);
+        }
+        self.next = 0;
+    }
+
+    /// Number of blocks the pool owns, shown or not.
+    pub fn block_count(&self) -> usize {
+        self.free.len() + self.active.len()
+    }
+
+    /// Shows the notes of `chart` that are in view at `song_time` and takes back the blocks
+    /// of those that have scrolled past. `references` are the hit-line blocks of the lanes,
+    /// `speed` is in world units per second.
+    pub fn update(
+        &mut self,
+        scene: &mut Scene,
+        chart: &Chart,
+        references: &[Handle<Node>],
+        song_time: f32,
+        speed: f32,
+        resource_manager: &ResourceManager,
+    ) {
+        //the song jumped back, notes that went past may be in view again
+        if song_time < self.time {
+            self.reset(scene);
+        }
+        self.time = song_time;
+        let speed = speed.max(f32::EPSILON);
+        let gone = song_time - VIEW_BEHIND / speed;
+        let coming = song_time + VIEW_AHEAD / speed;
+
+        let mut index = 0;
+        while index < self.active.len() {
+            let (note, block) = self.active[index];
+            let passed = chart
+                .notes
+                .get(note)
+                .map_or(true, |note| note.time + note.hold < gone);
+            if passed {
+                self.active.swap_remove(index);
+                self.release(scene, block);
+            } else {
+                index += 1;
+            }
+        }
+
+        while let Some(note) = chart.notes.get(self.next).copied() {
+            if note.time > coming {
+                break;
+            }
+            let id = self.next;
+            self.next += 1;
+            if note.time + note.hold < gone {
+                continue;
+            }
+            let Some(reference) = references.get(note.lane.index()).copied() else {
+                continue;
+            };
+            let Some(hit_line) = scene
+                .graph
+                .try_get(reference)
+                .map(|reference| reference.local_transform().position().clone())
+            else {
+                continue;
+            };
+
+            let block = self
+                .free
+                .pop()
+                .unwrap_or_else(|| rhythm::spawn_note_block(scene, resource_manager));
+            let node = &mut scene.graph[block];
+            node.set_visibility(true);
+            //the script keeps it in place from its next update on
+            node.local_transform_mut().set_position(Vector3::new(
+                hit_line.x,
+                hit_line.y + (note.time - song_time) * speed,
+                hit_line.z,
+            ));
+            if let Some(script) = node.try_get_script_mut::<RhythmBlock>() {
+                *script = RhythmBlock::new(id, note.lane, reference, note.time, note.hold);
+            }
+            if let Some(rectangle) = node.cast_mut::<Rectangle>() {
+                let sprite = chart.layout.sprite(note.lane);
+                rectangle.set_texture(Some(resource_manager.request::<Texture>(sprite)));
+            }
+            self.active.push((id, block));
+        }
+    }
+
+    //hides a block and empties its script until it is handed to another note
+    fn release(&mut self, scene: &mut Scene, block: Handle<Node>) {
+        if let Some(node) = scene.graph.try_get_mut(block) {
+            node.set_visibility(false);
+            if let Some(script) = node.try_get_script_mut::<RhythmBlock>() {
+                *script = RhythmBlock::default();
+            }
+            self.free.push(block);
+        }
+    }
+}
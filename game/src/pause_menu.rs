@@ -0,0 +1,157 @@
+//! Escape menu shown on top of the game while it is paused.
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        message::{MessageDirection, UiMessage},
+        stack_panel::StackPanelBuilder,
+        text::TextBuilder,
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+};
+
+/// What the player picked in the pause menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseAction {
+    Resume,
+    Fishdex,
+    Settings,
+    Practice,
+    Save,
+    Load,
+    Quit,
+}
+
+pub struct PauseMenu {
+    root: Handle<UiNode>,
+    resume: Handle<UiNode>,
+    fishdex: Handle<UiNode>,
+    settings: Handle<UiNode>,
+    practice: Handle<UiNode>,
+    save: Handle<UiNode>,
+    load: Handle<UiNode>,
+    quit: Handle<UiNode>,
+    open: bool,
+}
+
+fn make_button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(40.0)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+impl PauseMenu {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let ctx = &mut ui.build_ctx();
+
+        let resume = make_button(ctx, "Resume");
+        let fishdex = make_button(ctx, "Fishdex");
+        let settings = make_button(ctx, "Settings");
+        let practice = make_button(ctx, "Practice");
+        let save = make_button(ctx, "Save");
+        let load = make_button(ctx, "Load");
+        let quit = make_button(ctx, "Quit");
+
+        let title = TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(8.0)))
+            .with_text("Paused")
+            .with_horizontal_text_alignment(HorizontalAlignment::Center)
+            .build(ctx);
+
+        let buttons = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(title)
+                .with_child(resume)
+                .with_child(fishdex)
+                .with_child(settings)
+                .with_child(practice)
+                .with_child(save)
+                .with_child(load)
+                .with_child(quit),
+        )
+        .build(ctx);
+
+        //dims the scene behind the menu and starts hidden
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 160)))
+                .with_child(buttons),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            resume,
+            fishdex,
+            settings,
+            practice,
+            save,
+            load,
+            quit,
+            open: false,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn set_open(&mut self, open: bool, ui: &UserInterface) {
+        self.open = open;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            open,
+        ));
+    }
+
+    pub fn toggle(&mut self, ui: &UserInterface) {
+        self.set_open(!self.open, ui);
+    }
+
+    /// Practice only makes sense while catching, so its button is hidden everywhere else.
+    pub fn set_practice_available(&self, available: bool, ui: &UserInterface) {
+        ui.send_message(WidgetMessage::visibility(
+            self.practice,
+            MessageDirection::ToWidget,
+            available,
+        ));
+    }
+
+    /// Turns button clicks into actions, ignoring messages from other widgets.
+    pub fn handle_ui_message(&self, message: &UiMessage) -> Option<PauseAction> {
+        if let Some(ButtonMessage::Click) = message.data() {
+            let destination = message.destination();
+            if destination == self.resume {
+                return Some(PauseAction::Resume);
+            } else if destination == self.fishdex {
+                return Some(PauseAction::Fishdex);
+            } else if destination == self.settings {
+                return Some(PauseAction::Settings);
+            } else if destination == self.practice {
+                return Some(PauseAction::Practice);
+            } else if destination == self.save {
+                return Some(PauseAction::Save);
+            } else if destination == self.load {
+                return Some(PauseAction::Load);
+            } else if destination == self.quit {
+                return Some(PauseAction::Quit);
+            }
+        }
+        None
+    }
+}
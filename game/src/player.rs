@@ -16,12 +16,17 @@ use fyrox::{
     plugin::{Plugin, PluginConstructor, PluginContext, PluginRegistrationContext},
     scene::{
         dim2::{rectangle::Rectangle, rigidbody::RigidBody},
+        graph::Graph,
         node::{Node},
         Scene, SceneLoader,
     },
     script::{ScriptContext, ScriptTrait},
 };
 use std::path::Path;
+use crate::{audio::Cue, Game};
+
+/// Seconds between two footsteps while walking.
+const FOOTSTEP_INTERVAL: f32 = 0.35;
 
 #[derive(Visit, Reflect, Debug, Clone, Default)]
 pub struct Player{
@@ -30,10 +35,36 @@ pub struct Player{
     move_right: bool,
     move_up: bool,
     move_down: bool,
+    //seconds until the next footstep is heard
+    #[visit(optional)]
+    #[reflect(hidden)]
+    footstep_timer: f32,
 }
 
 impl_component_provider!(Player,);
 
+/// Finds the node that has the player script on it, if the graph has one.
+pub fn find_player(graph: &Graph) -> Handle<Node> {
+    graph
+        .pair_iter()
+        .find(|(_, node)| node.try_get_script::<Player>().is_some())
+        .map(|(handle, _)| handle)
+        .unwrap_or_default()
+}
+
+impl Player {
+    /// Starts or stops walking for a movement key, other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyCode, pressed: bool) {
+        match key {
+            KeyCode::KeyA => self.move_left = pressed,
+            KeyCode::KeyD => self.move_right = pressed,
+            KeyCode::KeyW => self.move_up = pressed,
+            KeyCode::KeyS => self.move_down = pressed,
+            _ => (),
+        }
+    }
+}
+
 impl TypeUuidProvider for Player {
     // Returns unique script id for serialization needs.
     fn type_uuid() -> Uuid {
@@ -53,12 +84,8 @@ impl ScriptTrait for Player {
         if let Event::WindowEvent { event, .. } = event {
             if let WindowEvent::KeyboardInput { event, .. } = event {
                 let is_pressed = event.state == ElementState::Pressed;
-                match event.physical_key {
-                    fyrox::keyboard::PhysicalKey::Code(KeyCode::KeyA) => self.move_left = is_pressed,
-                    fyrox::keyboard::PhysicalKey::Code(KeyCode::KeyD) => self.move_right = is_pressed,
-                    fyrox::keyboard::PhysicalKey::Code(KeyCode::KeyW) => self.move_up = is_pressed,
-                    fyrox::keyboard::PhysicalKey::Code(KeyCode::KeyS) => self.move_down = is_pressed,
-                    _ => (),
+                if let fyrox::keyboard::PhysicalKey::Code(key) = event.physical_key {
+                    self.handle_key(key, is_pressed);
                 }
             }
         }
@@ -68,7 +95,14 @@ impl ScriptTrait for Player {
     fn on_update(&mut self, context: &mut ScriptContext) {
         // The script can be assigned to any scene node, but we assert that it will work only with
         // 2d rigid body nodes.
+        let paused = context.plugins.get::<Game>().is_paused();
         if let Some(rigid_body) = context.scene.graph[context.handle].cast_mut::<RigidBody>() {
+            //stands still while a menu is open
+            if paused {
+                rigid_body.set_lin_vel(Vector2::new(0.0, 0.0));
+                return;
+            }
+
             let x_speed = match (self.move_left, self.move_right) {
                 (true, false) => 3.0,
                 (false, true) => -3.0,
@@ -81,6 +115,17 @@ impl ScriptTrait for Player {
             };
 
             rigid_body.set_lin_vel(Vector2::new(x_speed, y_speed));
+
+            //the first step is heard right away, then one every interval
+            if x_speed == 0.0 && y_speed == 0.0 {
+                self.footstep_timer = 0.0;
+            } else {
+                self.footstep_timer -= context.dt;
+                if self.footstep_timer <= 0.0 {
+                    self.footstep_timer += FOOTSTEP_INTERVAL;
+                    context.plugins.get_mut::<Game>().play_sound(Cue::Footstep);
+                }
+            }
         }
     }
 
@@ -0,0 +1,139 @@
+//! Practice mode: loop one section of a chart, slow it down and optionally never fail.
+//!
+//! Charts are split into sections of four bars counted from the first beat. Judgements are
+//! collected per section so the results screen can show where the player struggles.
+use crate::{chart::Chart, judge::Judgement, scoring::CatchStats};
+
+/// Length of one practice section in beats.
+pub const SECTION_BEATS: f32 = 16.0;
+/// Speeds the player can pick from, from half speed up to 90%.
+///
+/// Only the chart slows down: its notes scroll by and are due later. No song is played along
+/// with a catch, and the music of the catching scene keeps its own tempo.
+pub const SPEEDS: [f32; 5] = [0.5, 0.6, 0.7, 0.8, 0.9];
+/// Beats of run-up before a looped section starts.
+const LEAD_IN_BEATS: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PracticeOptions {
+    /// Section to loop, or `None` to play the whole chart once.
+    pub section: Option<usize>,
+    /// Multiplier on how fast the chart plays, one of [`SPEEDS`].
+    pub speed: f32,
+    /// Running out of progress no longer loses the fish.
+    pub no_fail: bool,
+}
+
+impl Default for PracticeOptions {
+    fn default() -> Self {
+        Self {
+            section: None,
+            speed: 0.8,
+            no_fail: true,
+        }
+    }
+}
+
+fn section_length(chart: &Chart) -> f32 {
+    SECTION_BEATS * chart.beat_length()
+}
+
+/// Number of sections the chart is split into, at least one.
+pub fn section_count(chart: &Chart) -> usize {
+    (((chart.duration() - chart.offset) / section_length(chart)).ceil() as usize).max(1)
+}
+
+/// Section a song time falls into. Times before the first beat belong to the first section.
+pub fn section_of(chart: &Chart, time: f32) -> usize {
+    let index = ((time - chart.offset) / section_length(chart)).floor().max(0.0) as usize;
+    index.min(section_count(chart) - 1)
+}
+
+/// Start and end of a section in song time.
+pub fn section_range(chart: &Chart, index: usize) -> (f32, f32) {
+    let start = chart.offset + index as f32 * section_length(chart);
+    (start, start + section_length(chart))
+}
+
+#[derive(Debug, Clone)]
+pub struct Practice {
+    options: PracticeOptions,
+    //the notes actually played, only those of the looped section when there is one
+    chart: Chart,
+    full_chart: Chart,
+    sections: Vec<CatchStats>,
+}
+
+impl Practice {
+    pub fn new(mut options: PracticeOptions, chart: &Chart) -> Self {
+        let count = section_count(chart);
+        options.section = options.section.filter(|section| *section < count);
+
+        let mut played = chart.clone();
+        if let Some(section) = options.section {
+            let (start, end) = section_range(chart, section);
+            played.notes.retain(|note| note.time >= start && note.time < end);
+        }
+
+        Self {
+            options,
+            chart: played,
+            full_chart: chart.clone(),
+            sections: vec![CatchStats::default(); count],
+        }
+    }
+
+    pub fn options(&self) -> PracticeOptions {
+        self.options
+    }
+
+    /// Notes being practised.
+    pub fn chart(&self) -> &Chart {
+        &self.chart
+    }
+
+    /// Song time the practice starts at, a little before the looped section.
+    pub fn start_time(&self) -> f32 {
+        match self.options.section {
+            Some(section) => {
+                let (start, _) = section_range(&self.full_chart, section);
+                start - LEAD_IN_BEATS * self.full_chart.beat_length()
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Song time to jump back to once the looped section is over and its last notes can no
+    /// longer be hit, `late_window` being how late a note may still be hit.
+    pub fn loop_restart(&self, time: f32, late_window: f32) -> Option<f32> {
+        let section = self.options.section?;
+        let (_, end) = section_range(&self.full_chart, section);
+        (time > end + late_window).then(|| self.start_time())
+    }
+
+    /// Counts a judgement towards the section of the note, `time` being the note's time in
+    /// the chart.
+    pub fn record(&mut self, time: f32, judgement: Judgement) {
+        let section = section_of(&self.full_chart, time);
+        self.sections[section].add(judgement);
+    }
+
+    /// Judgements collected in every section, in chart order.
+    pub fn sections(&self) -> &[CatchStats] {
+        &self.sections
+    }
+
+    /// Judgements of every section together.
+    pub fn total(&self) -> CatchStats {
+        self.sections
+            .iter()
+            .fold(CatchStats::default(), |total, section| CatchStats {
+                greats: total.greats + section.greats,
+                goods: total.goods + section.goods,
+                oks: total.oks + section.oks,
+                misses: total.misses + section.misses,
+                combo: 0,
+                max_combo: total.max_combo.max(section.max_combo),
+            })
+    }
+}
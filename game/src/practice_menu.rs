@@ -0,0 +1,308 @@
+//! Screen for setting up a [`Practice`](crate::practice::Practice) run of the current catch.
+use crate::practice::{PracticeOptions, SPEEDS};
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+        VerticalAlignment,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PracticeOption {
+    Section,
+    Speed,
+    NoFail,
+}
+
+const OPTIONS: [PracticeOption; 3] = [
+    PracticeOption::Section,
+    PracticeOption::Speed,
+    PracticeOption::NoFail,
+];
+
+impl PracticeOption {
+    fn label(self) -> &'static str {
+        match self {
+            PracticeOption::Section => "Section",
+            PracticeOption::Speed => "Chart speed",
+            PracticeOption::NoFail => "No fail",
+        }
+    }
+
+    fn value(self, options: &PracticeOptions, section_count: usize) -> String {
+        match self {
+            PracticeOption::Section => match options.section {
+                Some(section) => format!("{} / {}", section + 1, section_count),
+                None => "Whole song".to_string(),
+            },
+            PracticeOption::Speed => format!("{:.0}%", options.speed * 100.0),
+            PracticeOption::NoFail => if options.no_fail { "On" } else { "Off" }.to_string(),
+        }
+    }
+
+    //direction is -1 for the left arrow and +1 for the right one
+    fn adjust(self, options: &mut PracticeOptions, section_count: usize, direction: i32) {
+        match self {
+            PracticeOption::Section => {
+                //the whole song sits before the first section
+                let current = options.section.map_or(0, |section| section as i32 + 1);
+                let next = (current + direction).rem_euclid(section_count as i32 + 1);
+                options.section = (next > 0).then(|| next as usize - 1);
+            }
+            PracticeOption::Speed => {
+                let current = SPEEDS
+                    .iter()
+                    .position(|speed| *speed == options.speed)
+                    .unwrap_or(SPEEDS.len() - 1) as i32;
+                let next = (current + direction).clamp(0, SPEEDS.len() as i32 - 1);
+                options.speed = SPEEDS[next as usize];
+            }
+            PracticeOption::NoFail => options.no_fail = !options.no_fail,
+        }
+    }
+}
+
+struct OptionRow {
+    option: PracticeOption,
+    decrease: Handle<UiNode>,
+    increase: Handle<UiNode>,
+    value: Handle<UiNode>,
+}
+
+/// What happened after the player interacted with the practice screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PracticeAction {
+    /// Restart the catch with these options.
+    Start(PracticeOptions),
+    /// Stop practising and show the results so far.
+    End,
+    Back,
+}
+
+pub struct PracticeMenu {
+    root: Handle<UiNode>,
+    rows: Vec<OptionRow>,
+    start: Handle<UiNode>,
+    end: Handle<UiNode>,
+    back: Handle<UiNode>,
+    draft: PracticeOptions,
+    section_count: usize,
+    open: bool,
+}
+
+fn make_option_button(
+    ctx: &mut BuildContext,
+    text: &str,
+    row: usize,
+    column: usize,
+) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(column)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn make_button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(120.0)
+            .with_height(36.0)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+impl PracticeMenu {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let ctx = &mut ui.build_ctx();
+        let draft = PracticeOptions::default();
+
+        let mut rows = Vec::new();
+        let mut children = Vec::new();
+        for (i, option) in OPTIONS.iter().enumerate() {
+            let label = TextBuilder::new(WidgetBuilder::new().on_row(i).on_column(0))
+                .with_text(option.label())
+                .with_vertical_text_alignment(VerticalAlignment::Center)
+                .build(ctx);
+            let decrease = make_option_button(ctx, "<", i, 1);
+            let value = TextBuilder::new(WidgetBuilder::new().on_row(i).on_column(2))
+                .with_text(option.value(&draft, 1))
+                .with_horizontal_text_alignment(HorizontalAlignment::Center)
+                .with_vertical_text_alignment(VerticalAlignment::Center)
+                .build(ctx);
+            let increase = make_option_button(ctx, ">", i, 3);
+
+            children.extend([label, decrease, value, increase]);
+            rows.push(OptionRow {
+                option: *option,
+                decrease,
+                increase,
+                value,
+            });
+        }
+
+        let grid = GridBuilder::new(WidgetBuilder::new().with_children(children))
+            .add_rows(OPTIONS.iter().map(|_| Row::strict(32.0)).collect())
+            .add_column(Column::strict(180.0))
+            .add_column(Column::strict(32.0))
+            .add_column(Column::strict(120.0))
+            .add_column(Column::strict(32.0))
+            .build(ctx);
+
+        let start = make_button(ctx, "Start");
+        let end = make_button(ctx, "End practice");
+        let back = make_button(ctx, "Back");
+
+        let buttons = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_child(start)
+                .with_child(end)
+                .with_child(back),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+
+        let title = TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(8.0)))
+            .with_text("Practice")
+            .with_horizontal_text_alignment(HorizontalAlignment::Center)
+            .build(ctx);
+
+        let content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(title)
+                .with_child(grid)
+                .with_child(buttons),
+        )
+        .build(ctx);
+
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(content),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            rows,
+            start,
+            end,
+            back,
+            draft,
+            section_count: 1,
+            open: false,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows the screen for a chart with `section_count` sections, starting from `options`.
+    /// The end button is only offered while `practising`.
+    pub fn open(
+        &mut self,
+        options: PracticeOptions,
+        section_count: usize,
+        practising: bool,
+        ui: &UserInterface,
+    ) {
+        self.draft = options;
+        self.section_count = section_count.max(1);
+        if self
+            .draft
+            .section
+            .is_some_and(|section| section >= self.section_count)
+        {
+            self.draft.section = None;
+        }
+        self.sync_values(ui);
+        ui.send_message(WidgetMessage::visibility(
+            self.end,
+            MessageDirection::ToWidget,
+            practising,
+        ));
+        self.set_visible(true, ui);
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.set_visible(false, ui);
+    }
+
+    fn set_visible(&mut self, visible: bool, ui: &UserInterface) {
+        self.open = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    fn sync_values(&self, ui: &UserInterface) {
+        for row in self.rows.iter() {
+            ui.send_message(TextMessage::text(
+                row.value,
+                MessageDirection::ToWidget,
+                row.option.value(&self.draft, self.section_count),
+            ));
+        }
+    }
+
+    pub fn handle_ui_message(
+        &mut self,
+        message: &UiMessage,
+        ui: &UserInterface,
+    ) -> Option<PracticeAction> {
+        if let Some(ButtonMessage::Click) = message.data() {
+            let destination = message.destination();
+            if destination == self.start {
+                return Some(PracticeAction::Start(self.draft));
+            } else if destination == self.end {
+                return Some(PracticeAction::End);
+            } else if destination == self.back {
+                return Some(PracticeAction::Back);
+            }
+
+            for row in self.rows.iter() {
+                let direction = if destination == row.decrease {
+                    -1
+                } else if destination == row.increase {
+                    1
+                } else {
+                    continue;
+                };
+                row.option
+                    .adjust(&mut self.draft, self.section_count, direction);
+                ui.send_message(TextMessage::text(
+                    row.value,
+                    MessageDirection::ToWidget,
+                    row.option.value(&self.draft, self.section_count),
+                ));
+                break;
+            }
+        }
+        None
+    }
+}
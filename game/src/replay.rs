@@ -0,0 +1,244 @@
+//! Recording and playback of the inputs of a catch.
+//!
+//! A replay stores which fish was on the line, everything the judge depends on and every lane
+//! press and release with its song time. Feeding the inputs back through the judge reproduces
+//! the catch exactly, as long as the chart has not changed since.
+//!
+//! Replays are a small binary file: a header followed by five bytes per input.
+use crate::{chart::Chart, difficulty::Difficulty, judge::HitWindows, lanes::Lane};
+use std::{
+    fmt::{Display, Formatter},
+    fs,
+    path::Path,
+};
+
+/// Where the last recorded catch is kept, relative to the working directory.
+pub const LAST_REPLAY_PATH: &str = "replays/last.fhr";
+
+const MAGIC: &[u8; 4] = b"FHRP";
+const REPLAY_VERSION: u16 = 1;
+//set on the lane byte of a press, cleared for a release
+const PRESSED_BIT: u8 = 0x80;
+
+/// One lane key going down or up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputEvent {
+    /// Song time of the input in seconds.
+    pub time: f32,
+    pub lane: Lane,
+    pub pressed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Replay {
+    /// Species id of the fish that was on the line.
+    pub species: String,
+    pub size: f32,
+    pub difficulty: Difficulty,
+    pub audio_offset_ms: i32,
+    /// See [`chart_checksum`].
+    pub chart_checksum: u64,
+    /// Ordered by time.
+    pub events: Vec<InputEvent>,
+}
+
+/// All possible errors that may occur while reading or writing a replay.
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    /// The file is not a replay at all.
+    BadMagic,
+    /// Written by a newer version of the game.
+    UnsupportedVersion(u16),
+    /// The file ends in the middle of a value.
+    Truncated,
+    /// A value is out of range, the name says which.
+    Invalid(&'static str),
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "Replay io error: {e}"),
+            ReplayError::BadMagic => write!(f, "File is not a replay."),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "Replay version {version} is not supported.")
+            }
+            ReplayError::Truncated => write!(f, "Replay file is truncated."),
+            ReplayError::Invalid(what) => write!(f, "Replay has an invalid {what}."),
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+//reads little endian values off the front of a byte slice
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        if self.0.len() < count {
+            return Err(ReplayError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(count);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let species = self.species.as_bytes();
+        let species = &species[..species.len().min(u8::MAX as usize)];
+        let difficulty = Difficulty::ALL
+            .iter()
+            .position(|d| *d == self.difficulty)
+            .unwrap_or_default();
+
+        let mut bytes = Vec::with_capacity(32 + species.len() + self.events.len() * 5);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.push(species.len() as u8);
+        bytes.extend_from_slice(species);
+        bytes.extend_from_slice(&self.size.to_le_bytes());
+        bytes.push(difficulty as u8);
+        bytes.extend_from_slice(&self.audio_offset_ms.to_le_bytes());
+        bytes.extend_from_slice(&self.chart_checksum.to_le_bytes());
+        bytes.extend_from_slice(&(self.events.len() as u32).to_le_bytes());
+        for event in self.events.iter() {
+            //times are stored bit for bit, rounding them would change judgements
+            bytes.extend_from_slice(&event.time.to_le_bytes());
+            let pressed = if event.pressed { PRESSED_BIT } else { 0 };
+            bytes.push(event.lane.index() as u8 | pressed);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = u16::from_le_bytes(reader.array()?);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+
+        let species_len = reader.u8()? as usize;
+        let species = String::from_utf8(reader.take(species_len)?.to_vec())
+            .map_err(|_| ReplayError::Invalid("species"))?;
+        let size = f32::from_le_bytes(reader.array()?);
+        let difficulty = *Difficulty::ALL
+            .get(reader.u8()? as usize)
+            .ok_or(ReplayError::Invalid("difficulty"))?;
+        let audio_offset_ms = i32::from_le_bytes(reader.array()?);
+        let chart_checksum = u64::from_le_bytes(reader.array()?);
+
+        let count = u32::from_le_bytes(reader.array()?) as usize;
+        let mut events = Vec::with_capacity(count.min(reader.0.len() / 5));
+        for _ in 0..count {
+            let time = f32::from_le_bytes(reader.array()?);
+            let packed = reader.u8()?;
+            let lane = Lane::from_index((packed & !PRESSED_BIT) as usize)
+                .ok_or(ReplayError::Invalid("lane"))?;
+            events.push(InputEvent {
+                time,
+                lane,
+                pressed: packed & PRESSED_BIT != 0,
+            });
+        }
+
+        Ok(Self {
+            species,
+            size,
+            difficulty,
+            audio_offset_ms,
+            chart_checksum,
+            events,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Writes the replay to disk, creating the parent directory if needed.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    /// Adds an input, keeping the events in order.
+    pub fn record(&mut self, time: f32, lane: Lane, pressed: bool) {
+        self.events.push(InputEvent {
+            time,
+            lane,
+            pressed,
+        });
+    }
+}
+
+/// Fingerprint of every note and of the hit windows they are judged with, used to tell
+/// whether a replay was recorded against the catch it is played back on.
+pub fn chart_checksum(chart: &Chart, windows: &HitWindows) -> u64 {
+    //FNV-1a over the bits of every value the judge looks at
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            hash = (hash ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    };
+    for note in chart.notes.iter() {
+        feed(&note.time.to_le_bytes());
+        feed(&[note.lane.index() as u8]);
+        feed(&note.hold.to_le_bytes());
+    }
+    for window in [windows.great, windows.good, windows.ok] {
+        feed(&window.to_le_bytes());
+    }
+    hash
+}
+
+/// Hands out the inputs of a replay as the song reaches them.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    next: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Inputs made up to song time `time` that have not been handed out yet.
+    pub fn due(&mut self, time: f32) -> &[InputEvent] {
+        let start = self.next;
+        let end = start
+            + self.replay.events[start..].partition_point(|event| event.time <= time);
+        self.next = end;
+        &self.replay.events[start..end]
+    }
+}
@@ -0,0 +1,259 @@
+//! Screen shown after a catch is won or lost, before going back to the overworld.
+use crate::scoring::{CatchStats, Grade};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        image::{ImageBuilder, ImageMessage},
+        message::{MessageDirection, UiMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+    resource::texture::Texture,
+};
+
+/// The fish that was landed, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LandedFish {
+    pub name: String,
+    pub sprite: String,
+    pub size: f32,
+    pub weight_grams: f32,
+    /// What the shop would pay for it.
+    pub value: u32,
+    pub new_record: bool,
+}
+
+/// Everything the results screen shows about one catch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatchResult {
+    pub won: bool,
+    pub fish: Option<LandedFish>,
+    pub stats: CatchStats,
+    pub grade: Grade,
+    pub money_earned: u32,
+    /// False for catches played by autoplay, from a replay or in practice, which leave no
+    /// trace.
+    pub recorded: bool,
+    /// Judgements per chart section of a practice run, empty outside of practice.
+    pub sections: Vec<CatchStats>,
+}
+
+//accuracy of every practised section, sections without notes are left blank
+fn section_summary(sections: &[CatchStats]) -> String {
+    let parts: Vec<String> = sections
+        .iter()
+        .enumerate()
+        .map(|(i, section)| {
+            if section.total() == 0 {
+                format!("{}: -", i + 1)
+            } else {
+                format!("{}: {:.0}%", i + 1, section.accuracy() * 100.0)
+            }
+        })
+        .collect();
+    format!("Accuracy per section  {}", parts.join("  "))
+}
+
+pub struct ResultsScreen {
+    root: Handle<UiNode>,
+    headline: Handle<UiNode>,
+    image: Handle<UiNode>,
+    fish: Handle<UiNode>,
+    judgements: Handle<UiNode>,
+    summary: Handle<UiNode>,
+    sections: Handle<UiNode>,
+    money: Handle<UiNode>,
+    record: Handle<UiNode>,
+    continue_button: Handle<UiNode>,
+    open: bool,
+}
+
+fn make_text(ctx: &mut BuildContext) -> Handle<UiNode> {
+    TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(4.0)))
+        .with_horizontal_text_alignment(HorizontalAlignment::Center)
+        .build(ctx)
+}
+
+impl ResultsScreen {
+    pub fn new(ui: &mut UserInterface) -> Self {
+        let ctx = &mut ui.build_ctx();
+
+        let headline = make_text(ctx);
+        let image = ImageBuilder::new(
+            WidgetBuilder::new()
+                .with_width(96.0)
+                .with_height(96.0)
+                .with_horizontal_alignment(HorizontalAlignment::Center),
+        )
+        .build(ctx);
+        let fish = make_text(ctx);
+        let judgements = make_text(ctx);
+        let summary = make_text(ctx);
+        let sections = make_text(ctx);
+        let money = make_text(ctx);
+        let record = make_text(ctx);
+        let continue_button = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(160.0)
+                .with_height(40.0)
+                .with_margin(Thickness::uniform(8.0)),
+        )
+        .with_text("Continue")
+        .build(ctx);
+
+        let content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(headline)
+                .with_child(image)
+                .with_child(fish)
+                .with_child(judgements)
+                .with_child(summary)
+                .with_child(sections)
+                .with_child(money)
+                .with_child(record)
+                .with_child(continue_button),
+        )
+        .build(ctx);
+
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(content),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            headline,
+            image,
+            fish,
+            judgements,
+            summary,
+            sections,
+            money,
+            record,
+            continue_button,
+            open: false,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(
+        &mut self,
+        result: &CatchResult,
+        resource_manager: &ResourceManager,
+        ui: &UserInterface,
+    ) {
+        let stats = &result.stats;
+        let (headline, fish, record) = match &result.fish {
+            Some(fish) => {
+                ui.send_message(ImageMessage::texture(
+                    self.image,
+                    MessageDirection::ToWidget,
+                    Some(resource_manager.request::<Texture>(&fish.sprite).into_untyped()),
+                ));
+                (
+                    format!("You caught a {}!", fish.name),
+                    format!(
+                        "{:.1} cm, {:.0} g - worth {}g",
+                        fish.size, fish.weight_grams, fish.value
+                    ),
+                    if fish.new_record { "New record!" } else { "" },
+                )
+            }
+            None => {
+                ui.send_message(ImageMessage::texture(
+                    self.image,
+                    MessageDirection::ToWidget,
+                    None,
+                ));
+                let headline = if result.sections.is_empty() {
+                    "The fish got away..."
+                } else {
+                    "Practice finished"
+                };
+                (headline.to_string(), String::new(), "")
+            }
+        };
+
+        let texts = [
+            (self.headline, headline),
+            (self.fish, fish),
+            (
+                self.judgements,
+                format!(
+                    "Great {}  Good {}  Ok {}  Miss {}",
+                    stats.greats, stats.goods, stats.oks, stats.misses
+                ),
+            ),
+            (
+                self.summary,
+                format!(
+                    "Max combo {}  Accuracy {:.1}%  Grade {:?}",
+                    stats.max_combo,
+                    stats.accuracy() * 100.0,
+                    result.grade
+                ),
+            ),
+            (
+                self.sections,
+                if result.sections.is_empty() {
+                    String::new()
+                } else {
+                    section_summary(&result.sections)
+                },
+            ),
+            (self.money, format!("Money earned: {}g", result.money_earned)),
+            (
+                self.record,
+                if !result.sections.is_empty() {
+                    "Practice - nothing was recorded".to_string()
+                } else if !result.recorded {
+                    "Autoplay or replay - nothing was recorded".to_string()
+                } else {
+                    record.to_string()
+                },
+            ),
+        ];
+        for (text, value) in texts {
+            ui.send_message(TextMessage::text(text, MessageDirection::ToWidget, value));
+        }
+
+        self.set_visible(true, ui);
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.set_visible(false, ui);
+    }
+
+    fn set_visible(&mut self, visible: bool, ui: &UserInterface) {
+        self.open = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    /// Returns true once the player presses continue.
+    pub fn handle_ui_message(&self, message: &UiMessage) -> bool {
+        matches!(message.data(), Some(ButtonMessage::Click))
+            && message.destination() == self.continue_button
+    }
+}
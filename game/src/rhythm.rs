@@ -2,6 +2,7 @@ use fyrox::{
     core::{
         algebra::{Vector2, Vector3},
         futures::executor::block_on,
+        log::Log,
         pool::Handle,
         reflect::prelude::*,
         uuid::{uuid, Uuid},
@@ -15,45 +16,182 @@ use fyrox::{
     gui::message::UiMessage,
     plugin::{Plugin, PluginConstructor, PluginContext, PluginRegistrationContext},
     scene::{
-        dim2::{rectangle::Rectangle, rigidbody::RigidBody},
+        base::BaseBuilder,
+        dim2::{rectangle::{Rectangle, RectangleBuilder}, rigidbody::RigidBody},
         node::{Node},
+        transform::TransformBuilder,
         Scene, SceneLoader,
     },
-    script::{ScriptContext, ScriptTrait, ScriptMessagePayload, ScriptMessageContext},
+    script::{Script, ScriptContext, ScriptTrait},
 };
 use std::path::Path;
+use crate::{
+    chart::NoteId,
+    judge::Judgement,
+    lanes::{Lane, LaneLayout},
+    scoring::{DEFAULT_GOAL, START_PROGRESS},
+    Game,
+};
+
+pub use crate::scoring::{CatchStats, Grade};
 
-const GOOD_DISTANCE: f32 = 0.2;
-const GREAT_DISTANCE: f32 = 0.1;
-const TOUCH_DISTANCE: f32 = 1.0;
+/// Width and height of a spawned note, in world units.
+const NOTE_SIZE: f32 = 0.8;
+/// How far the progress bar moves for one point of progress, in world units.
+const PROGRESS_STEP: f32 = 0.05;
 
 #[derive(Visit, Reflect, Debug, Clone, Default)]
 pub struct RhythmBlock{
-    speed: f32,
     reference_block: Handle<Node>,
-    good: bool,
-    great: bool,
-    touching: bool, 
-    clicked: bool,
-    active: bool,
-    next_block: Handle<Node>,
+    #[visit(optional)]
+    judged: bool,
+    //id the judge knows the note by, hand-placed blocks are handed one on their first update
+    #[visit(optional)]
+    note: Option<NoteId>,
+    #[visit(optional)]
+    lane: Option<Lane>,
+    //seconds into the song at which the block reaches the hit line. blocks spawned from a
+    //chart are placed by the song clock, hand-placed ones scroll on their own
+    #[visit(optional)]
+    time: Option<f32>,
+    //length of a hold note in seconds
+    #[visit(optional)]
+    hold: f32,
+    //direction flags from before lanes existed, only read to convert old scenes
+    #[reflect(hidden)]
     up: bool,
+    #[reflect(hidden)]
     down: bool,
+    #[reflect(hidden)]
     right: bool,
+    #[reflect(hidden)]
     left: bool,
 }
 
-enum Message {
-    GreatClick,
-    GoodClick,
-    OkClick,
-    BadClick,
-    Win,
-    Loss,
-    Activate (Handle<Node>),
+impl_component_provider!(RhythmBlock);
+
+impl RhythmBlock {
+    /// Block for chart note `note` that reaches `reference_block` at `time` seconds into the
+    /// song.
+    pub fn new(
+        note: NoteId,
+        lane: Lane,
+        reference_block: Handle<Node>,
+        time: f32,
+        hold: f32,
+    ) -> Self {
+        Self {
+            reference_block,
+            note: Some(note),
+            lane: Some(lane),
+            time: Some(time),
+            hold,
+            ..Default::default()
+        }
+    }
 }
 
-impl_component_provider!(RhythmBlock);
+/// Clears the hand-placed blocks out of the catching scene and returns one hit-line block per
+/// lane of `layout`, ready for [`NotePool`](crate::note_pool::NotePool).
+///
+/// The hit line is taken from the reference blocks the hand-placed blocks point at. When
+/// there is one per lane they keep marking the lanes, otherwise new markers are spawned
+/// along the same line. Returns nothing if the scene has no reference blocks.
+pub fn setup_lanes(
+    scene: &mut Scene,
+    layout: LaneLayout,
+    resource_manager: &ResourceManager,
+) -> Vec<Handle<Node>> {
+    let mut placed = Vec::new();
+    let mut references = Vec::new();
+    for (handle, node) in scene.graph.pair_iter() {
+        if let Some(block) = node.try_get_script::<RhythmBlock>() {
+            placed.push(handle);
+            if !references.contains(&block.reference_block) {
+                references.push(block.reference_block);
+            }
+        }
+    }
+    references.retain(|reference| scene.graph.try_get(*reference).is_some());
+    if references.is_empty() {
+        Log::err("The catching scene has no reference blocks, keeping its own notes.");
+        return Vec::new();
+    }
+    references.sort_by(|a, b| {
+        let a = scene.graph[*a].global_position().x;
+        let b = scene.graph[*b].global_position().x;
+        a.total_cmp(&b)
+    });
+
+    for handle in placed {
+        scene.graph.remove_node(handle);
+    }
+
+    if references.len() != layout.lane_count() {
+        let hit_line = scene.graph[references[0]].local_transform().position().clone();
+        let centre = references
+            .iter()
+            .map(|reference| scene.graph[*reference].local_transform().position().x)
+            .sum::<f32>()
+            / references.len() as f32;
+        references = layout
+            .lanes()
+            .iter()
+            .map(|lane| {
+                let position = Vector3::new(
+                    centre + layout.x_position(*lane),
+                    hit_line.y,
+                    hit_line.z,
+                );
+                spawn_rectangle(
+                    scene,
+                    position,
+                    Some(layout.sprite(*lane)),
+                    resource_manager,
+                    None,
+                )
+            })
+            .collect();
+    }
+    references
+}
+
+/// Spawns a hidden note block with an empty script, ready to be handed a chart note by
+/// [`NotePool`](crate::note_pool::NotePool).
+pub fn spawn_note_block(scene: &mut Scene, resource_manager: &ResourceManager) -> Handle<Node> {
+    let block = spawn_rectangle(
+        scene,
+        Vector3::default(),
+        None,
+        resource_manager,
+        Some(Script::new(RhythmBlock::default())),
+    );
+    scene.graph[block].set_visibility(false);
+    block
+}
+
+fn spawn_rectangle(
+    scene: &mut Scene,
+    position: Vector3<f32>,
+    sprite: Option<&str>,
+    resource_manager: &ResourceManager,
+    script: Option<Script>,
+) -> Handle<Node> {
+    let mut base = BaseBuilder::new().with_local_transform(
+        TransformBuilder::new()
+            .with_local_position(position)
+            .with_local_scale(Vector3::new(NOTE_SIZE, NOTE_SIZE, 1.0))
+            .build(),
+    );
+    if let Some(script) = script {
+        base = base.with_script(script);
+    }
+    let mut builder = RectangleBuilder::new(base);
+    if let Some(sprite) = sprite {
+        builder = builder.with_texture(resource_manager.request::<Texture>(sprite));
+    }
+    builder.build(&mut scene.graph)
+}
 
 impl TypeUuidProvider for RhythmBlock {
     // Returns unique script id for serialization needs.
@@ -68,136 +206,121 @@ impl ScriptTrait for RhythmBlock {
     
     // Put start logic - it is called when every other script is already initialized.
     fn on_start(&mut self, context: &mut ScriptContext) {
-        self.touching = false;
-        self.good = false;
-        self.great = false;
-        self.clicked = false;
-        context.message_dispatcher.subscribe_to::<Message>(context.handle);
-    }
+        self.judged = false;
 
-    // Called whenever there is an event from OS (mouse click, keypress, etc.)
-    fn on_os_event(&mut self, event: &Event<()>, context: &mut ScriptContext) {
-        if self.active{
-            if let Event::WindowEvent { event, .. } = event {
-                if let WindowEvent::KeyboardInput { event, .. } = event {
-                    let is_pressed = event.state == ElementState::Pressed;
-                    match event.physical_key {
-                        fyrox::keyboard::PhysicalKey::Code(KeyCode::ArrowUp) => {
-                            if self.up {
-                                self.clicked = is_pressed;
-                            }
-                        }
-                        fyrox::keyboard::PhysicalKey::Code(KeyCode::ArrowDown) => {
-                            if self.down {
-                                self.clicked = is_pressed;
-                            }
-                        }
-                        fyrox::keyboard::PhysicalKey::Code(KeyCode::ArrowRight) => {
-                            if self.right {
-                                self.clicked = is_pressed;
-                            }
-                        }
-                        fyrox::keyboard::PhysicalKey::Code(KeyCode::ArrowLeft) => {
-                            if self.left {
-                                self.clicked = is_pressed;
-                            }
-                        }
-                        _ => (),
-                    }
-                }
-            }
+        //blocks saved with the old direction flags map onto the four arrow-key lanes
+        if self.lane.is_none() {
+            self.lane = match (self.left, self.down, self.up, self.right) {
+                (true, _, _, _) => Some(Lane::One),
+                (_, true, _, _) => Some(Lane::Two),
+                (_, _, true, _) => Some(Lane::Three),
+                (_, _, _, true) => Some(Lane::Four),
+                _ => None,
+            };
         }
     }
 
     // Called every frame at fixed rate of 60 FPS.
     fn on_update(&mut self, context: &mut ScriptContext) {
+        let game = context.plugins.get::<Game>();
+        //notes stop scrolling while the game is paused
+        if game.is_paused() {
+            return;
+        }
+        //speed comes from the difficulty of the current catch, in units per second
+        let speed = game.rhythm_params().scroll_speed * game.settings().scroll_speed;
+        let audio_offset = game.audio_offset();
+        let song_time = game.song_time();
+        //the chart editor only shows notes, unless it is previewing the chart
+        let judging = game.notes_judged();
+
         if let Some(ref_block) = context.scene.graph.try_get(self.reference_block){
             //gets position of reference block
             let ref_position = ref_block.local_transform().position().clone();
 
             //script only works for rectangles
             if let Some(rectangle) = context.scene.graph[context.handle].cast_mut::<Rectangle>() {
-                //moves block consistently downwards at a rate corresponding to its speed
                 let transform = rectangle.local_transform_mut();
-                let offset_down = Vector3::new(0.0, self.speed*-0.01, 0.0);
-                transform.offset(offset_down);
-
-                //touching reference block
-                if self.touching == false && (rectangle.local_transform().position().y <= ref_position.y + TOUCH_DISTANCE) {
-                    self.touching = true;
-                }
-
-                //close enough to ref block to be "good"
-                if self.good == false && (rectangle.local_transform().position().y <= ref_position.y + GOOD_DISTANCE) {
-                    self.good = true;
-                }
-
-                //close enough to be "great"
-                if self.great == false && (rectangle.local_transform().position().y <= ref_position.y + GREAT_DISTANCE) {
-                    self.good = true;
-                }
-
-                //farther and no longer great
-                if self.great == true && (rectangle.local_transform().position().y <= ref_position.y - GREAT_DISTANCE) {
-                    self.great = false;
-                }
+                let distance = match self.time {
+                    //chart notes sit wherever the song clock says, holds stretch upwards
+                    Some(time) => {
+                        let distance = (time - song_time) * speed;
+                        let length = self.hold * speed;
+                        let position = transform.position().clone();
+                        transform.set_position(Vector3::new(
+                            position.x,
+                            ref_position.y + distance + length / 2.0,
+                            position.z,
+                        ));
+                        transform.set_scale(Vector3::new(NOTE_SIZE, NOTE_SIZE + length, 1.0));
+                        distance
+                    }
+                    //moves block consistently downwards at a rate corresponding to its speed
+                    None => {
+                        let offset_down = Vector3::new(0.0, -speed * context.dt, 0.0);
+                        transform.offset(offset_down);
+                        transform.position().y - ref_position.y
+                    }
+                };
 
-                //farther and no longer "good"
-                if self.good == true && (rectangle.local_transform().position().y <= ref_position.y - GOOD_DISTANCE) {
-                    self.good = false;
+                let Some(lane) = self.lane else {
+                    return;
+                };
+                if self.judged || !judging {
+                    return;
                 }
 
-                //farther and no longer "touching"
-                if self.touching == true && (rectangle.local_transform().position().y <= ref_position.y - TOUCH_DISTANCE) {
-                    self.touching = false;
+                let game = context.plugins.get_mut::<Game>();
+                let note = *self.note.get_or_insert_with(|| game.allocate_note_id());
+                //chart notes are tracked by the game when spawned, hand-placed blocks tell the
+                //session when they reach the hit line. late audio shifts the ideal moment later
+                if self.time.is_none() {
+                    let hit_time = song_time + distance / speed.max(f32::EPSILON) + audio_offset;
+                    game.track_note(note, lane, hit_time);
                 }
-
-                if self.active {
-                    if self.clicked == true {
-                        //block disappears
+                //picks up the judgement once the note is hit or has scrolled past, the game
+                //announces it itself, see GameEvent::Judged
+                if let Some(judgement) = game.session().judgement(note) {
+                    self.judged = true;
+                    if judgement != Judgement::Miss {
+                        //block disappears, the hit feedback takes it from there
                         rectangle.set_visibility(false);
-
-                        //sends message depending on how good click was
-                        if self.great {
-                            context.message_sender.send_global(Message::GreatClick);
-                        } else if self.good {
-                            context.message_sender.send_global(Message::GoodClick);
-                        } else if self.touching {
-                            context.message_sender.send_global(Message::OkClick);
-                        } else {
-                            context.message_sender.send_global(Message::BadClick);
-                        }
-
-                        //activates next block and deactivates itself
-                        context.message_sender.send_global(Message::Activate(self.next_block));
-                        self.active = false;
                     }
                 }
             }
         }
     }
 
-    fn on_message(&mut self, message: &mut dyn ScriptMessagePayload, ctx: &mut ScriptMessageContext) {
-        //responds to activation after prev block is clicked
-        if let Some(Message::Activate(node_handle)) = message.downcast_ref::<Message>() {
-            if node_handle.index() == ctx.handle.index() {
-                self.active = true;
-            }
-        }
-    }
-
     // Returns unique script ID for serialization needs.
     fn id(&self) -> Uuid {
         Self::type_uuid()
     }
 }
 
+/// Progress needed to land the fish in `scene`: the bar has to climb up to the block its
+/// progress bar points at. Scenes without one use [`DEFAULT_GOAL`].
+pub fn progress_goal(scene: &Scene) -> f32 {
+    scene
+        .graph
+        .pair_iter()
+        .find_map(|(_, node)| {
+            let bar = node.try_get_script::<ProgressBar>()?;
+            let reference = scene.graph.try_get(bar.reference_block)?;
+            let distance =
+                reference.local_transform().position().y - node.local_transform().position().y;
+            Some(START_PROGRESS + distance / PROGRESS_STEP)
+        })
+        .unwrap_or(DEFAULT_GOAL)
+}
+
+/// Shows the progress of the catch being played. The game keeps the score in its
+/// [`CatchSession`](crate::session::CatchSession), the bar only follows it.
 #[derive(Visit, Reflect, Default, Debug, Clone)]
 pub struct ProgressBar {
-    progress: f32,
-    new_progress: f32,
-    move_amount: f32,
     reference_block: Handle<Node>,
+    //height of the bar at the start of the catch
+    #[visit(optional)]
+    start_y: f32,
 }
 
 impl_component_provider!(ProgressBar);
@@ -211,14 +334,11 @@ impl TypeUuidProvider for ProgressBar {
 impl ScriptTrait for ProgressBar {
     fn on_init(&mut self, context: &mut ScriptContext) {
         // Put initialization logic here.
-        self.progress = 5.0;
-        self.new_progress = 0.0;
-        self.move_amount = 0.05;
+        self.start_y = context.scene.graph[context.handle].local_transform().position().y;
     }
     
     fn on_start(&mut self, context: &mut ScriptContext) {
         // Put start logic - it is called when every other script is already initialized.
-        context.message_dispatcher.subscribe_to::<Message>(context.handle);
     }
 
     fn on_os_event(&mut self, event: &Event<()>, context: &mut ScriptContext) {
@@ -226,49 +346,19 @@ impl ScriptTrait for ProgressBar {
     }
 
     fn on_update(&mut self, context: &mut ScriptContext) {
-        if let Some(ref_block) = context.scene.graph.try_get(self.reference_block){
-            //gets position of reference block
-            let ref_position = ref_block.local_transform().position().clone();
-            
-            //accesses its own rectangle node
-            if let Some(rectangle) = context.scene.graph[context.handle].cast_mut::<Rectangle>() {
-                //updates if it should go up or down
-                if self.new_progress != 0.0 {
-                    //moves progress bar
-                    let transform = rectangle.local_transform_mut();
-                    let y_offset = self.new_progress*self.move_amount;
-                    let offset_down = Vector3::new(0.0, y_offset, 0.0);
-                    transform.offset(offset_down);
-
-                    //changes progress
-                    self.progress += self.new_progress;
-                    self.new_progress = 0.0;
-
-                    //checks if win or loss
-                    if rectangle.local_transform().position().y >= ref_position.y {
-                        context.message_sender.send_global(Message::Win);
-                    } else if self.progress <= 0.0 {
-                        context.message_sender.send_global(Message::Loss);
-                    }
-                }
-            }
+        let game = context.plugins.get::<Game>();
+        if game.is_paused() || game.is_editing() {
+            return;
         }
-    }
+        //every hit pulls the bar up, every miss lets it slip
+        let value = game.session().progress().value();
 
-    fn on_message(&mut self, message: &mut dyn ScriptMessagePayload, ctx: &mut ScriptMessageContext,
-    ) {
-        // React to clicks.
-        if let Some(Message::GreatClick) = message.downcast_ref::<Message>() {
-            self.new_progress = 4.0;
-        }
-        if let Some(Message::GoodClick) = message.downcast_ref::<Message>() {
-            self.new_progress = 2.0;
-        }
-        if let Some(Message::OkClick) = message.downcast_ref::<Message>() {
-            self.new_progress = 1.0;
-        }
-        if let Some(Message::BadClick) = message.downcast_ref::<Message>() {
-            self.new_progress = -1.0;
+        //moves the bar to match the progress
+        if let Some(rectangle) = context.scene.graph[context.handle].cast_mut::<Rectangle>() {
+            let transform = rectangle.local_transform_mut();
+            let position = transform.position().clone();
+            let y = self.start_y + (value - START_PROGRESS) * PROGRESS_STEP;
+            transform.set_position(Vector3::new(position.x, y, position.z));
         }
     }
 
@@ -0,0 +1,269 @@
+//! Save files and their format versions.
+//!
+//! Saves are written as RON with a top-level `version` field. Whenever the layout of
+//! [`SaveData`] changes, bump [`SAVE_VERSION`], move the previous layout into [`legacy`]
+//! with an `upgrade` method that produces the next version, add a step to [`upgrade`] and
+//! drop a fixture of the old layout into `tests/fixtures/saves`. Legacy layouts only embed
+//! frozen copies of the types they were saved with, so changing a live type never changes
+//! how an old save parses.
+use crate::{fish::FishLog, shop::Inventory};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    fs,
+    path::Path,
+};
+
+/// Version written into every new save.
+pub const SAVE_VERSION: u32 = 3;
+
+/// Default location of the save slot, relative to the working directory.
+pub const SAVE_PATH: &str = "saves/slot0.ron";
+
+/// Everything that is persisted between play sessions, in the current layout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveData {
+    pub version: u32,
+    pub player: PlayerSave,
+    pub fish_log: FishLog,
+    pub inventory: Inventory,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            player: PlayerSave::default(),
+            fish_log: FishLog::default(),
+            inventory: Inventory::default(),
+        }
+    }
+}
+
+/// Persisted state of the [`crate::player::Player`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct PlayerSave {
+    pub position: (f32, f32),
+}
+
+/// Layouts of older save versions. Each one knows how to upgrade itself to the next version.
+pub mod legacy {
+    use super::{PlayerSave, SaveData};
+    use crate::{
+        fish::{CatchRecord, FishLog},
+        scoring::Grade,
+        shop::Inventory,
+    };
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    /// Player state as saved by versions 1 and 2.
+    #[derive(Deserialize, Debug, Clone, PartialEq)]
+    pub struct PlayerV1 {
+        pub position: (f32, f32),
+    }
+
+    impl PlayerV1 {
+        pub fn upgrade(self) -> PlayerSave {
+            PlayerSave {
+                position: self.position,
+            }
+        }
+    }
+
+    /// Catch grades as saved by version 2.
+    #[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+    pub enum GradeV2 {
+        D,
+        C,
+        B,
+        A,
+        S,
+    }
+
+    impl GradeV2 {
+        pub fn upgrade(self) -> Grade {
+            match self {
+                GradeV2::D => Grade::D,
+                GradeV2::C => Grade::C,
+                GradeV2::B => Grade::B,
+                GradeV2::A => Grade::A,
+                GradeV2::S => Grade::S,
+            }
+        }
+    }
+
+    /// One species of the fish collection log as saved by version 2.
+    #[derive(Deserialize, Debug, Clone, PartialEq)]
+    pub struct CatchRecordV2 {
+        pub count: u32,
+        pub largest_size: f32,
+        pub best_grade: Option<GradeV2>,
+        pub first_caught: u64,
+    }
+
+    impl CatchRecordV2 {
+        pub fn upgrade(self) -> CatchRecord {
+            CatchRecord {
+                count: self.count,
+                largest_size: self.largest_size,
+                best_grade: self.best_grade.map(GradeV2::upgrade),
+                first_caught: self.first_caught,
+            }
+        }
+    }
+
+    /// Fish collection log as saved by version 2.
+    #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+    pub struct FishLogV2 {
+        pub records: BTreeMap<String, CatchRecordV2>,
+    }
+
+    impl FishLogV2 {
+        pub fn upgrade(self) -> FishLog {
+            FishLog {
+                records: self
+                    .records
+                    .into_iter()
+                    .map(|(id, record)| (id, record.upgrade()))
+                    .collect(),
+            }
+        }
+    }
+
+    /// Version 1: only the player position.
+    #[derive(Deserialize, Debug, Clone, PartialEq)]
+    pub struct SaveV1 {
+        pub version: u32,
+        pub player: PlayerV1,
+    }
+
+    impl SaveV1 {
+        /// Version 2 added the fish collection log, which starts out empty.
+        pub fn upgrade(self) -> SaveV2 {
+            SaveV2 {
+                version: 2,
+                player: self.player,
+                fish_log: FishLogV2::default(),
+            }
+        }
+    }
+
+    /// Version 2: player position and the fish collection log.
+    #[derive(Deserialize, Debug, Clone, PartialEq)]
+    pub struct SaveV2 {
+        pub version: u32,
+        pub player: PlayerV1,
+        pub fish_log: FishLogV2,
+    }
+
+    impl SaveV2 {
+        /// Version 3 added money and the inventory. Old saves get the starting money and an
+        /// empty bag.
+        pub fn upgrade(self) -> SaveData {
+            SaveData {
+                version: 3,
+                player: self.player.upgrade(),
+                fish_log: self.fish_log.upgrade(),
+                inventory: Inventory::default(),
+            }
+        }
+    }
+}
+
+/// All possible errors that may occur while reading or writing a save.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The file could not be read or written.
+    Io(std::io::Error),
+    /// The file is not valid RON or does not match the layout of its version.
+    Parse(ron::error::SpannedError),
+    /// The save could not be serialized.
+    Serialize(ron::Error),
+    /// The save was written by a newer build of the game.
+    TooNew(u32),
+    /// The save has a version that never existed.
+    UnknownVersion(u32),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "Save file io error: {e}"),
+            SaveError::Parse(e) => write!(f, "Save file is malformed: {e}"),
+            SaveError::Serialize(e) => write!(f, "Unable to serialize save: {e}"),
+            SaveError::TooNew(v) => write!(
+                f,
+                "Save has version {v}, but this build only supports up to {SAVE_VERSION}."
+            ),
+            SaveError::UnknownVersion(v) => write!(f, "Save has unknown version {v}."),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        SaveError::Parse(e)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(e: ron::Error) -> Self {
+        SaveError::Serialize(e)
+    }
+}
+
+//only used to peek at the version before picking a layout to parse with
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+/// Parses a save of any supported version and upgrades it to the current layout.
+pub fn load_from_str(text: &str) -> Result<SaveData, SaveError> {
+    let header: SaveHeader = ron::from_str(text)?;
+    upgrade(text, header.version)
+}
+
+/// Reads a save from disk and upgrades it to the current layout.
+pub fn load_from_file(path: impl AsRef<Path>) -> Result<SaveData, SaveError> {
+    load_from_str(&fs::read_to_string(path)?)
+}
+
+/// Serializes a save, always using the current version.
+pub fn save_to_string(data: &SaveData) -> Result<String, SaveError> {
+    let data = SaveData {
+        version: SAVE_VERSION,
+        ..data.clone()
+    };
+    Ok(ron::ser::to_string_pretty(&data, PrettyConfig::default())?)
+}
+
+/// Writes a save to disk, creating the parent directory if needed.
+pub fn save_to_file(path: impl AsRef<Path>, data: &SaveData) -> Result<(), SaveError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, save_to_string(data)?)?;
+    Ok(())
+}
+
+//parses the layout of the given version and runs it through every upgrade step up to the
+//current one
+fn upgrade(text: &str, version: u32) -> Result<SaveData, SaveError> {
+    match version {
+        SAVE_VERSION => Ok(ron::from_str(text)?),
+        1 => Ok(ron::from_str::<legacy::SaveV1>(text)?.upgrade().upgrade()),
+        2 => Ok(ron::from_str::<legacy::SaveV2>(text)?.upgrade()),
+        v if v > SAVE_VERSION => Err(SaveError::TooNew(v)),
+        v => Err(SaveError::UnknownVersion(v)),
+    }
+}
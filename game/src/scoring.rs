@@ -0,0 +1,169 @@
+//! Scoring of a catch: judgement counts, grades and the progress bar that decides whether
+//! the fish is landed.
+//!
+//! Nothing in here touches the scene, so it can be driven and tested without the engine
+//! running. The progress bar script only mirrors [`Progress`] on screen.
+use crate::judge::Judgement;
+use fyrox::core::{reflect::prelude::*, visitor::prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Progress a catch starts with.
+pub const START_PROGRESS: f32 = 5.0;
+/// Progress needed to land the fish when the scene does not say otherwise.
+pub const DEFAULT_GOAL: f32 = 25.0;
+
+/// Letter grade of a finished catch, ordered from worst to best.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Grade {
+    D,
+    C,
+    B,
+    A,
+    S,
+}
+
+impl Grade {
+    /// Grade for a weighted hit rate as returned by [`CatchStats::accuracy`].
+    pub fn from_accuracy(accuracy: f32) -> Self {
+        match accuracy {
+            a if a >= 0.95 => Grade::S,
+            a if a >= 0.85 => Grade::A,
+            a if a >= 0.70 => Grade::B,
+            a if a >= 0.55 => Grade::C,
+            _ => Grade::D,
+        }
+    }
+}
+
+/// Judgement counts collected over one catch.
+#[derive(Visit, Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct CatchStats {
+    pub greats: u32,
+    pub goods: u32,
+    pub oks: u32,
+    pub misses: u32,
+    pub combo: u32,
+    pub max_combo: u32,
+}
+
+impl CatchStats {
+    /// Counts one judgement, keeping the combo up to date.
+    pub fn add(&mut self, judgement: Judgement) {
+        match judgement {
+            Judgement::Great => self.greats += 1,
+            Judgement::Good => self.goods += 1,
+            Judgement::Ok => self.oks += 1,
+            Judgement::Miss => {
+                self.misses += 1;
+                self.combo = 0;
+                return;
+            }
+        }
+        self.combo += 1;
+        self.max_combo = self.max_combo.max(self.combo);
+    }
+
+    pub fn total(&self) -> u32 {
+        self.greats + self.goods + self.oks + self.misses
+    }
+
+    /// Weighted hit rate in `0.0..=1.0`, where a Great counts fully, a Good for two thirds
+    /// and an Ok for one third.
+    pub fn accuracy(&self) -> f32 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        let score = self.greats as f32 + self.goods as f32 * 2.0 / 3.0 + self.oks as f32 / 3.0;
+        score / total as f32
+    }
+
+    pub fn grade(&self) -> Grade {
+        Grade::from_accuracy(self.accuracy())
+    }
+}
+
+/// How a catch ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+}
+
+/// How much a judgement moves the progress bar.
+pub fn progress_delta(judgement: Judgement) -> f32 {
+    match judgement {
+        Judgement::Great => 4.0,
+        Judgement::Good => 2.0,
+        Judgement::Ok => 1.0,
+        Judgement::Miss => -1.0,
+    }
+}
+
+/// The tug of war between the player and the fish. Hits pull the bar up, misses let it
+/// slip; the fish is landed once the bar reaches its goal and gets away once it is empty.
+#[derive(Visit, Reflect, Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    value: f32,
+    goal: f32,
+    no_fail: bool,
+    decided: bool,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new(DEFAULT_GOAL)
+    }
+}
+
+impl Progress {
+    /// Starts at [`START_PROGRESS`], the fish is landed once the bar reaches `goal`.
+    pub fn new(goal: f32) -> Self {
+        Self {
+            value: START_PROGRESS,
+            goal,
+            no_fail: false,
+            decided: false,
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn goal(&self) -> f32 {
+        self.goal
+    }
+
+    /// With no-fail on, the bar bottoms out at zero instead of losing the fish.
+    pub fn set_no_fail(&mut self, no_fail: bool) {
+        self.no_fail = no_fail;
+    }
+
+    /// Moves the bar for one judgement. Returns the outcome once, on the judgement that
+    /// decides the catch; the bar no longer moves after that.
+    pub fn apply(&mut self, judgement: Judgement) -> Option<Outcome> {
+        if self.decided {
+            return None;
+        }
+        self.value += progress_delta(judgement);
+        if self.no_fail {
+            self.value = self.value.max(0.0);
+        }
+
+        let outcome = self.outcome();
+        self.decided = outcome.is_some();
+        outcome
+    }
+
+    /// Whether the catch is won or lost at the current value.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.value >= self.goal {
+            Some(Outcome::Won)
+        } else if self.value <= 0.0 && !self.no_fail {
+            Some(Outcome::Lost)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! A whole catch played out from song times and lane inputs alone.
+//!
+//! [`CatchSession`] ties a chart to the judge, the judgement counts and the progress bar.
+//! Inputs and the passing of time go in, judgements and the outcome come out, so a catch
+//! can be simulated or checked without a scene. The game drives one of these for every catch
+//! and the scene only shows what it decided.
+use crate::{
+    chart::Chart,
+    judge::{HitWindows, Judged, Judgement, NoteJudge},
+    lanes::Lane,
+    replay::InputEvent,
+    scoring::{CatchStats, Outcome, Progress},
+};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct CatchSession {
+    chart: Chart,
+    //notes are keyed by their index in the chart, notes tracked on top of it get keys past
+    //its end
+    judge: NoteJudge<usize>,
+    judgements: HashMap<usize, Judgement>,
+    stats: CatchStats,
+    progress: Progress,
+    outcome: Option<Outcome>,
+    audio_offset: f32,
+    time: f32,
+}
+
+impl CatchSession {
+    /// Starts a catch of `chart` at song time zero. `audio_offset` is added to the ideal
+    /// moment of every note, as for audio that arrives late.
+    pub fn new(chart: Chart, windows: HitWindows, audio_offset: f32, progress: Progress) -> Self {
+        let mut judge = NoteJudge::default();
+        judge.set_windows(windows);
+        let mut session = Self {
+            chart: Chart::default(),
+            judge,
+            judgements: HashMap::new(),
+            stats: CatchStats::default(),
+            progress,
+            outcome: None,
+            audio_offset,
+            time: 0.0,
+        };
+        session.restart(chart, 0.0);
+        session
+    }
+
+    /// Plays `chart` from song time `time` on, forgetting every judgement made so far. The
+    /// counts, the progress bar and the outcome carry over, as when a practice section loops.
+    pub fn restart(&mut self, chart: Chart, time: f32) {
+        self.judge.clear();
+        for (index, note) in chart.notes.iter().enumerate() {
+            self.judge
+                .track_hold(index, note.lane, note.time + self.audio_offset, note.hold);
+        }
+        self.judgements.clear();
+        self.chart = chart;
+        self.time = time;
+    }
+
+    /// Judges an extra note that is not part of the chart, or moves one it already knows.
+    /// `note` has to be past the end of the chart, `hit_time` includes the audio offset.
+    pub fn track(&mut self, note: usize, lane: Lane, hit_time: f32) {
+        self.judge.track(note, lane, hit_time);
+    }
+
+    pub fn chart(&self) -> &Chart {
+        &self.chart
+    }
+
+    /// Song time the session has been advanced to.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn stats(&self) -> &CatchStats {
+        &self.stats
+    }
+
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Set by the judgement that decided the catch, and kept from then on.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Judgement of the note at `index` in the chart, once it has been hit or missed.
+    pub fn judgement(&self, index: usize) -> Option<Judgement> {
+        self.judgements.get(&index).copied()
+    }
+
+    /// Whether every note of the chart has been judged.
+    pub fn is_complete(&self) -> bool {
+        (0..self.chart.notes.len()).all(|index| self.judgements.contains_key(&index))
+    }
+
+    /// Moves the song on to `time`, missing every note that can no longer be hit. Time never
+    /// goes backwards, earlier times are ignored.
+    pub fn advance(&mut self, time: f32) -> Vec<Judged<usize>> {
+        if time > self.time {
+            self.time = time;
+        }
+        self.judge.expire(self.time);
+        self.collect()
+    }
+
+    /// A lane key going down at song time `time`. Notes that expired before the press are
+    /// missed first, so the returned judgements are in the order they happened.
+    pub fn press(&mut self, lane: Lane, time: f32) -> Vec<Judged<usize>> {
+        let mut judged = self.advance(time);
+        self.judge.press(lane, self.time);
+        judged.extend(self.collect());
+        judged
+    }
+
+    /// A lane key going up at song time `time`, ending the hold held in that lane.
+    pub fn release(&mut self, lane: Lane, time: f32) -> Vec<Judged<usize>> {
+        let mut judged = self.advance(time);
+        self.judge.release(lane, self.time);
+        judged.extend(self.collect());
+        judged
+    }
+
+    /// Feeds one recorded input.
+    pub fn input(&mut self, event: &InputEvent) -> Vec<Judged<usize>> {
+        if event.pressed {
+            self.press(event.lane, event.time)
+        } else {
+            self.release(event.lane, event.time)
+        }
+    }
+
+    /// Plays every input in order and then lets the song run until every note is judged.
+    pub fn run(&mut self, events: &[InputEvent]) -> Vec<Judged<usize>> {
+        let mut judged: Vec<_> = events.iter().flat_map(|event| self.input(event)).collect();
+        //well past the window of the last note, which only expires once strictly outside it
+        let end = self.chart.duration() + self.audio_offset + 2.0 * self.judge.windows().ok;
+        judged.extend(self.advance(end));
+        judged
+    }
+
+    //books the judge's latest judgements into the counts and the progress bar
+    fn collect(&mut self) -> Vec<Judged<usize>> {
+        let judged = self.judge.take_judged();
+        for entry in judged.iter() {
+            self.judgements.insert(entry.note, entry.judgement);
+            self.stats.add(entry.judgement);
+            if let Some(outcome) = self.progress.apply(entry.judgement) {
+                self.outcome = Some(outcome);
+            }
+        }
+        judged
+    }
+}
@@ -0,0 +1,154 @@
+//! Player-facing options, persisted to a config file next to the game.
+use crate::difficulty::Difficulty;
+use fyrox::{
+    core::{algebra::Matrix3, log::Log, pool::Handle},
+    dpi::PhysicalSize,
+    engine::{GraphicsContext, GraphicsContextParams},
+    gui::{
+        message::MessageDirection, widget::WidgetMessage, UiNode, UserInterface,
+    },
+    window::{Fullscreen, WindowAttributes},
+};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Default location of the config file, relative to the working directory.
+pub const SETTINGS_PATH: &str = "settings.ron";
+
+/// Resolutions offered by the settings screen when running windowed.
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    Fullscreen,
+}
+
+/// Every option the player can change. Missing fields in the config file fall back to
+/// their defaults, so new options never invalidate an old config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Volumes are linear gains in `0.0..=1.0`.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// Menu clicks and other interface sounds.
+    pub ui_volume: f32,
+    pub window_mode: WindowMode,
+    /// Window size used in [`WindowMode::Windowed`].
+    pub resolution: (u32, u32),
+    /// Read by [`Self::graphics_context_params`] when the graphics context is created, so it
+    /// only takes effect after a restart.
+    pub vsync: bool,
+    pub ui_scale: f32,
+    /// Multiplier applied to the speed of every rhythm note.
+    pub scroll_speed: f32,
+    /// Combined with each fish's rating to pick note speed, density and hit windows.
+    pub difficulty: Difficulty,
+    /// Shifts the moment notes are judged, positive values for audio that arrives late.
+    pub audio_offset_ms: i32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+            ui_volume: 0.8,
+            window_mode: WindowMode::Windowed,
+            resolution: RESOLUTIONS[0],
+            vsync: true,
+            ui_scale: 1.0,
+            scroll_speed: 1.0,
+            difficulty: Difficulty::default(),
+            audio_offset_ms: 0,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the config file, falling back to defaults if it is missing or broken.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(text) => match ron::from_str(&text) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    Log::err(format!("Invalid settings file {}: {e}", path.display()));
+                    Self::default()
+                }
+            },
+            //first launch, nothing saved yet
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let result = ron::ser::to_string_pretty(self, PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(path.as_ref(), text).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            Log::err(format!("Unable to save settings: {e}"));
+        }
+    }
+
+    /// Effective music gain once the master volume is taken into account.
+    pub fn music_gain(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    /// Effective sound effect gain once the master volume is taken into account.
+    pub fn sfx_gain(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    /// Effective interface sound gain once the master volume is taken into account.
+    pub fn ui_gain(&self) -> f32 {
+        self.master_volume * self.ui_volume
+    }
+
+    /// Parameters for the graphics context created at startup, the only place vsync is read.
+    pub fn graphics_context_params(&self, title: &str) -> GraphicsContextParams {
+        GraphicsContextParams {
+            window_attributes: WindowAttributes {
+                title: title.to_string(),
+                resizable: true,
+                inner_size: Some(PhysicalSize::new(self.resolution.0, self.resolution.1).into()),
+                ..Default::default()
+            },
+            vsync: self.vsync,
+        }
+    }
+
+    /// Applies the window mode and resolution. Does nothing before the window exists.
+    pub fn apply_window(&self, graphics_context: &GraphicsContext) {
+        if let GraphicsContext::Initialized(graphics_context) = graphics_context {
+            let window = &graphics_context.window;
+            match self.window_mode {
+                WindowMode::Windowed => {
+                    window.set_fullscreen(None);
+                    let (width, height) = self.resolution;
+                    let _ = window.request_inner_size(PhysicalSize::new(width, height));
+                }
+                WindowMode::Fullscreen => {
+                    window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+                }
+            }
+        }
+    }
+
+    /// Scales the given menu roots by [`Self::ui_scale`].
+    pub fn apply_ui_scale(&self, ui: &UserInterface, roots: &[Handle<UiNode>]) {
+        for root in roots {
+            ui.send_message(WidgetMessage::layout_transform(
+                *root,
+                MessageDirection::ToWidget,
+                Matrix3::new_scaling(self.ui_scale),
+            ));
+        }
+    }
+}
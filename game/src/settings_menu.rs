@@ -0,0 +1,337 @@
+//! In-game screen for editing [`Settings`].
+use crate::{
+    difficulty::Difficulty,
+    settings::{Settings, WindowMode, RESOLUTIONS},
+};
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+        VerticalAlignment,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingOption {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    UiVolume,
+    WindowMode,
+    Resolution,
+    Vsync,
+    UiScale,
+    ScrollSpeed,
+    Difficulty,
+    AudioOffset,
+}
+
+const OPTIONS: [SettingOption; 11] = [
+    SettingOption::MasterVolume,
+    SettingOption::MusicVolume,
+    SettingOption::SfxVolume,
+    SettingOption::UiVolume,
+    SettingOption::WindowMode,
+    SettingOption::Resolution,
+    SettingOption::Vsync,
+    SettingOption::UiScale,
+    SettingOption::ScrollSpeed,
+    SettingOption::Difficulty,
+    SettingOption::AudioOffset,
+];
+
+impl SettingOption {
+    fn label(self) -> &'static str {
+        match self {
+            SettingOption::MasterVolume => "Master volume",
+            SettingOption::MusicVolume => "Music volume",
+            SettingOption::SfxVolume => "SFX volume",
+            SettingOption::UiVolume => "UI volume",
+            SettingOption::WindowMode => "Window mode",
+            SettingOption::Resolution => "Resolution",
+            SettingOption::Vsync => "VSync (applies on restart)",
+            SettingOption::UiScale => "UI scale",
+            SettingOption::ScrollSpeed => "Scroll speed",
+            SettingOption::Difficulty => "Difficulty",
+            SettingOption::AudioOffset => "Audio offset",
+        }
+    }
+
+    fn value(self, settings: &Settings) -> String {
+        match self {
+            SettingOption::MasterVolume => format!("{:.0}%", settings.master_volume * 100.0),
+            SettingOption::MusicVolume => format!("{:.0}%", settings.music_volume * 100.0),
+            SettingOption::SfxVolume => format!("{:.0}%", settings.sfx_volume * 100.0),
+            SettingOption::UiVolume => format!("{:.0}%", settings.ui_volume * 100.0),
+            SettingOption::WindowMode => format!("{:?}", settings.window_mode),
+            SettingOption::Resolution => format!("{}x{}", settings.resolution.0, settings.resolution.1),
+            SettingOption::Vsync => if settings.vsync { "On" } else { "Off" }.to_string(),
+            SettingOption::UiScale => format!("{:.2}x", settings.ui_scale),
+            SettingOption::ScrollSpeed => format!("{:.1}x", settings.scroll_speed),
+            SettingOption::Difficulty => format!("{:?}", settings.difficulty),
+            SettingOption::AudioOffset => format!("{} ms", settings.audio_offset_ms),
+        }
+    }
+
+    //direction is -1 for the left arrow and +1 for the right one
+    fn adjust(self, settings: &mut Settings, direction: i32) {
+        let step = direction as f32;
+        match self {
+            SettingOption::MasterVolume => {
+                settings.master_volume = (settings.master_volume + 0.1 * step).clamp(0.0, 1.0)
+            }
+            SettingOption::MusicVolume => {
+                settings.music_volume = (settings.music_volume + 0.1 * step).clamp(0.0, 1.0)
+            }
+            SettingOption::SfxVolume => {
+                settings.sfx_volume = (settings.sfx_volume + 0.1 * step).clamp(0.0, 1.0)
+            }
+            SettingOption::UiVolume => {
+                settings.ui_volume = (settings.ui_volume + 0.1 * step).clamp(0.0, 1.0)
+            }
+            SettingOption::WindowMode => {
+                settings.window_mode = match settings.window_mode {
+                    WindowMode::Windowed => WindowMode::Fullscreen,
+                    WindowMode::Fullscreen => WindowMode::Windowed,
+                }
+            }
+            SettingOption::Resolution => {
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|r| *r == settings.resolution)
+                    .unwrap_or(0) as i32;
+                let next = (current + direction).rem_euclid(RESOLUTIONS.len() as i32);
+                settings.resolution = RESOLUTIONS[next as usize];
+            }
+            SettingOption::Vsync => settings.vsync = !settings.vsync,
+            SettingOption::UiScale => {
+                settings.ui_scale = (settings.ui_scale + 0.25 * step).clamp(0.5, 2.0)
+            }
+            SettingOption::ScrollSpeed => {
+                settings.scroll_speed = (settings.scroll_speed + 0.1 * step).clamp(0.5, 3.0)
+            }
+            SettingOption::Difficulty => {
+                let current = Difficulty::ALL
+                    .iter()
+                    .position(|d| *d == settings.difficulty)
+                    .unwrap_or(0) as i32;
+                let next = (current + direction).rem_euclid(Difficulty::ALL.len() as i32);
+                settings.difficulty = Difficulty::ALL[next as usize];
+            }
+            SettingOption::AudioOffset => {
+                settings.audio_offset_ms = (settings.audio_offset_ms + 5 * direction).clamp(-300, 300)
+            }
+        }
+    }
+}
+
+struct OptionRow {
+    option: SettingOption,
+    decrease: Handle<UiNode>,
+    increase: Handle<UiNode>,
+    value: Handle<UiNode>,
+}
+
+/// What happened after the player interacted with the settings screen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsAction {
+    /// The player confirmed these settings.
+    Apply(Settings),
+    Back,
+}
+
+pub struct SettingsMenu {
+    root: Handle<UiNode>,
+    rows: Vec<OptionRow>,
+    apply: Handle<UiNode>,
+    back: Handle<UiNode>,
+    //edited copy, only handed out once the player presses apply
+    draft: Settings,
+    open: bool,
+}
+
+fn make_button(ctx: &mut BuildContext, text: &str, row: usize, column: usize) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(column)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+impl SettingsMenu {
+    pub fn new(ui: &mut UserInterface, settings: &Settings) -> Self {
+        let ctx = &mut ui.build_ctx();
+
+        let mut rows = Vec::new();
+        let mut children = Vec::new();
+        for (i, option) in OPTIONS.iter().enumerate() {
+            let label = TextBuilder::new(WidgetBuilder::new().on_row(i).on_column(0))
+                .with_text(option.label())
+                .with_vertical_text_alignment(VerticalAlignment::Center)
+                .build(ctx);
+            let decrease = make_button(ctx, "<", i, 1);
+            let value = TextBuilder::new(WidgetBuilder::new().on_row(i).on_column(2))
+                .with_text(option.value(settings))
+                .with_horizontal_text_alignment(HorizontalAlignment::Center)
+                .with_vertical_text_alignment(VerticalAlignment::Center)
+                .build(ctx);
+            let increase = make_button(ctx, ">", i, 3);
+
+            children.extend([label, decrease, value, increase]);
+            rows.push(OptionRow {
+                option: *option,
+                decrease,
+                increase,
+                value,
+            });
+        }
+
+        let grid = GridBuilder::new(WidgetBuilder::new().with_children(children))
+            .add_rows(OPTIONS.iter().map(|_| Row::strict(32.0)).collect())
+            .add_column(Column::strict(180.0))
+            .add_column(Column::strict(32.0))
+            .add_column(Column::strict(120.0))
+            .add_column(Column::strict(32.0))
+            .build(ctx);
+
+        let apply = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(120.0)
+                .with_height(36.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_text("Apply")
+        .build(ctx);
+        let back = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(120.0)
+                .with_height(36.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_text("Back")
+        .build(ctx);
+
+        let buttons = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_child(apply)
+                .with_child(back),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+
+        let title = TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(8.0)))
+            .with_text("Settings")
+            .with_horizontal_text_alignment(HorizontalAlignment::Center)
+            .build(ctx);
+
+        let content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(title)
+                .with_child(grid)
+                .with_child(buttons),
+        )
+        .build(ctx);
+
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(content),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            rows,
+            apply,
+            back,
+            draft: settings.clone(),
+            open: false,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows the screen, starting the edit from the given settings.
+    pub fn open(&mut self, settings: &Settings, ui: &UserInterface) {
+        self.draft = settings.clone();
+        self.sync_values(ui);
+        self.set_visible(true, ui);
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.set_visible(false, ui);
+    }
+
+    fn set_visible(&mut self, visible: bool, ui: &UserInterface) {
+        self.open = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    fn sync_values(&self, ui: &UserInterface) {
+        for row in self.rows.iter() {
+            ui.send_message(TextMessage::text(
+                row.value,
+                MessageDirection::ToWidget,
+                row.option.value(&self.draft),
+            ));
+        }
+    }
+
+    pub fn handle_ui_message(
+        &mut self,
+        message: &UiMessage,
+        ui: &UserInterface,
+    ) -> Option<SettingsAction> {
+        if let Some(ButtonMessage::Click) = message.data() {
+            let destination = message.destination();
+            if destination == self.apply {
+                return Some(SettingsAction::Apply(self.draft.clone()));
+            } else if destination == self.back {
+                return Some(SettingsAction::Back);
+            }
+
+            for row in self.rows.iter() {
+                let direction = if destination == row.decrease {
+                    -1
+                } else if destination == row.increase {
+                    1
+                } else {
+                    continue;
+                };
+                row.option.adjust(&mut self.draft, direction);
+                ui.send_message(TextMessage::text(
+                    row.value,
+                    MessageDirection::ToWidget,
+                    row.option.value(&self.draft),
+                ));
+                break;
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,230 @@
+//! Money, the player's inventory, shop prices and the shopkeeper the player walks up to.
+use crate::{
+    fish::{FishRegistry, FishSpecies},
+    player,
+    scoring::Grade,
+    Game,
+};
+use fyrox::{
+    core::{
+        log::Log,
+        reflect::prelude::*,
+        uuid::{uuid, Uuid},
+        visitor::prelude::*,
+        TypeUuidProvider,
+    },
+    event::{ElementState, Event, WindowEvent},
+    impl_component_provider,
+    keyboard::KeyCode,
+    script::{ScriptContext, ScriptTrait},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Default location of the shop catalogue, relative to the working directory.
+pub const SHOP_CATALOGUE_PATH: &str = "data/shop.ron";
+
+/// Money the player starts a new game with.
+pub const STARTING_MONEY: u32 = 50;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Rod,
+    Hook,
+    Bait,
+}
+
+/// Something the shop sells.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShopItem {
+    pub id: String,
+    pub name: String,
+    pub kind: ItemKind,
+    pub price: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ShopCatalogue {
+    pub items: Vec<ShopItem>,
+}
+
+impl ShopCatalogue {
+    /// Reads the catalogue, logging and returning an empty one if the file is broken.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|e| e.to_string()));
+        match result {
+            Ok(catalogue) => catalogue,
+            Err(e) => {
+                Log::err(format!("Unable to load shop catalogue {}: {e}", path.display()));
+                Self::default()
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ShopItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+}
+
+/// A fish sitting in the player's bag, waiting to be sold.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CaughtFish {
+    pub species: String,
+    pub size: f32,
+    pub grade: Option<Grade>,
+}
+
+/// Money, bought items and unsold fish.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Inventory {
+    pub money: u32,
+    /// Item id to how many of it the player owns.
+    pub items: BTreeMap<String, u32>,
+    pub fish: Vec<CaughtFish>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            money: STARTING_MONEY,
+            items: BTreeMap::new(),
+            fish: Vec::new(),
+        }
+    }
+}
+
+/// Why a purchase did not go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShopError {
+    NotEnoughMoney,
+    UnknownItem,
+    /// Rods and hooks can only be bought once.
+    AlreadyOwned,
+}
+
+impl Inventory {
+    pub fn count(&self, item_id: &str) -> u32 {
+        self.items.get(item_id).copied().unwrap_or(0)
+    }
+
+    pub fn buy(&mut self, catalogue: &ShopCatalogue, item_id: &str) -> Result<(), ShopError> {
+        let item = catalogue.get(item_id).ok_or(ShopError::UnknownItem)?;
+        if item.kind != ItemKind::Bait && self.count(item_id) > 0 {
+            return Err(ShopError::AlreadyOwned);
+        }
+        if self.money < item.price {
+            return Err(ShopError::NotEnoughMoney);
+        }
+
+        self.money -= item.price;
+        *self.items.entry(item_id.to_string()).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// Sells the fish at `index` in the bag and returns how much it was worth. Fish of a species
+    /// the registry doesn't know stay in the bag, there is no price to pay for them.
+    pub fn sell_fish(&mut self, registry: &FishRegistry, index: usize) -> Option<u32> {
+        let fish = self.fish.get(index)?;
+        let price = fish_price(registry.get(&fish.species)?, fish.size, fish.grade);
+        self.fish.remove(index);
+        self.money += price;
+        Some(price)
+    }
+}
+
+/// What the shop pays for a fish: the species' base price, scaled up to 1.5x for the biggest
+/// specimens and by how cleanly it was caught.
+pub fn fish_price(species: &FishSpecies, size: f32, grade: Option<Grade>) -> u32 {
+    let range = species.max_size - species.min_size;
+    let size_factor = if range > 0.0 {
+        0.75 + 0.75 * ((size - species.min_size) / range).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let grade_factor = match grade {
+        Some(Grade::S) => 1.5,
+        Some(Grade::A) => 1.25,
+        Some(Grade::B) | None => 1.0,
+        Some(Grade::C) => 0.9,
+        Some(Grade::D) => 0.75,
+    };
+    (species.base_price as f32 * size_factor * grade_factor).round() as u32
+}
+
+/// Money paid straight away for landing a fish cleanly, on top of what it sells for.
+pub fn catch_bonus(grade: Grade) -> u32 {
+    match grade {
+        Grade::S => 20,
+        Grade::A => 10,
+        Grade::B => 5,
+        Grade::C | Grade::D => 0,
+    }
+}
+
+/// Opens the shop when the player stands close enough and presses E.
+#[derive(Visit, Reflect, Debug, Clone)]
+pub struct Shopkeeper {
+    /// How close the player has to be, in world units.
+    interact_radius: f32,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    interact_pressed: bool,
+}
+
+impl Default for Shopkeeper {
+    fn default() -> Self {
+        Self {
+            interact_radius: 1.5,
+            interact_pressed: false,
+        }
+    }
+}
+
+impl_component_provider!(Shopkeeper);
+
+impl TypeUuidProvider for Shopkeeper {
+    // Returns unique script id for serialization needs.
+    fn type_uuid() -> Uuid {
+        uuid!("b7d85fd5-496e-4c36-86f8-1b6039c358f9")
+    }
+}
+
+impl ScriptTrait for Shopkeeper {
+    fn on_os_event(&mut self, event: &Event<()>, _context: &mut ScriptContext) {
+        if let Event::WindowEvent { event: WindowEvent::KeyboardInput { event, .. }, .. } = event {
+            if event.physical_key == fyrox::keyboard::PhysicalKey::Code(KeyCode::KeyE)
+                && event.state == ElementState::Pressed
+                && !event.repeat
+            {
+                self.interact_pressed = true;
+            }
+        }
+    }
+
+    fn on_update(&mut self, context: &mut ScriptContext) {
+        if !std::mem::take(&mut self.interact_pressed) {
+            return;
+        }
+        if context.plugins.get::<Game>().is_paused() {
+            return;
+        }
+
+        //looks for the player and checks that they are standing next to the shop
+        let graph = &context.scene.graph;
+        let position = graph[context.handle].global_position();
+        if let Some(player) = graph.try_get(player::find_player(graph)) {
+            let offset = player.global_position() - position;
+            if offset.xy().norm() <= self.interact_radius {
+                context.plugins.get_mut::<Game>().request_shop();
+            }
+        }
+    }
+
+    // Returns unique script ID for serialization needs.
+    fn id(&self) -> Uuid {
+        Self::type_uuid()
+    }
+}
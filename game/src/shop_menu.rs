@@ -0,0 +1,304 @@
+//! Shop screen for buying gear and selling caught fish.
+use crate::{
+    fish::FishRegistry,
+    shop::{self, Inventory, ShopCatalogue, ShopError},
+};
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        scroll_viewer::ScrollViewerBuilder,
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+        VerticalAlignment,
+    },
+};
+
+/// What happened after the player interacted with the shop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShopAction {
+    /// An item was bought, by its id in the catalogue.
+    Bought(String),
+    /// A fish was sold for this much money.
+    Sold(u32),
+    Close,
+}
+
+struct BuyRow {
+    item_id: String,
+    buy: Handle<UiNode>,
+    owned: Handle<UiNode>,
+}
+
+pub struct ShopMenu {
+    root: Handle<UiNode>,
+    money: Handle<UiNode>,
+    status: Handle<UiNode>,
+    buy_rows: Vec<BuyRow>,
+    sell_panel: Handle<UiNode>,
+    //one button per fish in the bag, in the same order as the inventory
+    sell_buttons: Vec<Handle<UiNode>>,
+    close: Handle<UiNode>,
+    open: bool,
+}
+
+fn make_text(ctx: &mut BuildContext, text: &str, row: usize, column: usize) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(column)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_text(text)
+    .with_vertical_text_alignment(VerticalAlignment::Center)
+    .build(ctx)
+}
+
+impl ShopMenu {
+    pub fn new(ui: &mut UserInterface, catalogue: &ShopCatalogue) -> Self {
+        let ctx = &mut ui.build_ctx();
+
+        let mut children = Vec::new();
+        let mut buy_rows = Vec::new();
+        for (row, item) in catalogue.items.iter().enumerate() {
+            let name = make_text(ctx, &item.name, row, 0);
+            let kind = make_text(ctx, &format!("{:?}", item.kind), row, 1);
+            let price = make_text(ctx, &format!("{}g", item.price), row, 2);
+            let owned = make_text(ctx, "", row, 3);
+            let buy = ButtonBuilder::new(
+                WidgetBuilder::new()
+                    .on_row(row)
+                    .on_column(4)
+                    .with_margin(Thickness::uniform(2.0)),
+            )
+            .with_text("Buy")
+            .build(ctx);
+
+            children.extend([name, kind, price, owned, buy]);
+            buy_rows.push(BuyRow {
+                item_id: item.id.clone(),
+                buy,
+                owned,
+            });
+        }
+
+        let buy_grid = GridBuilder::new(WidgetBuilder::new().with_children(children))
+            .add_rows(catalogue.items.iter().map(|_| Row::strict(30.0)).collect())
+            .add_column(Column::strict(150.0))
+            .add_column(Column::strict(60.0))
+            .add_column(Column::strict(60.0))
+            .add_column(Column::strict(80.0))
+            .add_column(Column::strict(60.0))
+            .build(ctx);
+
+        let sell_panel = StackPanelBuilder::new(WidgetBuilder::new()).build(ctx);
+        let sell_list = ScrollViewerBuilder::new(
+            WidgetBuilder::new()
+                .with_width(300.0)
+                .with_height(300.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_content(sell_panel)
+        .build(ctx);
+
+        let buy_column = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::uniform(4.0))
+                .with_child(make_text(ctx, "Buy", 0, 0))
+                .with_child(buy_grid),
+        )
+        .build(ctx);
+        let sell_column = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::uniform(4.0))
+                .with_child(make_text(ctx, "Sell (click a fish)", 0, 0))
+                .with_child(sell_list),
+        )
+        .build(ctx);
+        let columns = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_child(buy_column)
+                .with_child(sell_column),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+
+        let title = TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(8.0)))
+            .with_text("Shop")
+            .with_horizontal_text_alignment(HorizontalAlignment::Center)
+            .build(ctx);
+        let money = make_text(ctx, "", 0, 0);
+        let status = make_text(ctx, "", 0, 0);
+        let close = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(120.0)
+                .with_height(36.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_text("Leave")
+        .build(ctx);
+
+        let content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(title)
+                .with_child(money)
+                .with_child(columns)
+                .with_child(status)
+                .with_child(close),
+        )
+        .build(ctx);
+
+        let root = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(content),
+        )
+        .build(ctx);
+
+        Self {
+            root,
+            money,
+            status,
+            buy_rows,
+            sell_panel,
+            sell_buttons: Vec::new(),
+            close,
+            open: false,
+        }
+    }
+
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, inventory: &Inventory, registry: &FishRegistry, ui: &mut UserInterface) {
+        self.set_status("", ui);
+        self.refresh(inventory, registry, ui);
+        self.set_visible(true, ui);
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.set_visible(false, ui);
+    }
+
+    fn set_visible(&mut self, visible: bool, ui: &UserInterface) {
+        self.open = visible;
+        ui.send_message(WidgetMessage::visibility(
+            self.root,
+            MessageDirection::ToWidget,
+            visible,
+        ));
+    }
+
+    fn set_status(&self, text: &str, ui: &UserInterface) {
+        ui.send_message(TextMessage::text(
+            self.status,
+            MessageDirection::ToWidget,
+            text.to_string(),
+        ));
+    }
+
+    //updates the money and owned counts and rebuilds the list of fish to sell
+    fn refresh(&mut self, inventory: &Inventory, registry: &FishRegistry, ui: &mut UserInterface) {
+        ui.send_message(TextMessage::text(
+            self.money,
+            MessageDirection::ToWidget,
+            format!("Money: {}g", inventory.money),
+        ));
+        for row in self.buy_rows.iter() {
+            ui.send_message(TextMessage::text(
+                row.owned,
+                MessageDirection::ToWidget,
+                format!("Owned: {}", inventory.count(&row.item_id)),
+            ));
+        }
+
+        for button in self.sell_buttons.drain(..) {
+            ui.send_message(WidgetMessage::remove(button, MessageDirection::ToWidget));
+        }
+        for fish in inventory.fish.iter() {
+            let (name, price) = match registry.get(&fish.species) {
+                Some(species) => (
+                    species.name.as_str(),
+                    shop::fish_price(species, fish.size, fish.grade),
+                ),
+                None => (fish.species.as_str(), 0),
+            };
+            let grade = fish
+                .grade
+                .map(|g| format!(" ({g:?})"))
+                .unwrap_or_default();
+            let button = ButtonBuilder::new(
+                WidgetBuilder::new()
+                    .with_height(30.0)
+                    .with_margin(Thickness::uniform(2.0)),
+            )
+            .with_text(&format!("{name} {:.1} cm{grade} - {price}g", fish.size))
+            .build(&mut ui.build_ctx());
+            ui.send_message(WidgetMessage::link(
+                button,
+                MessageDirection::ToWidget,
+                self.sell_panel,
+            ));
+            self.sell_buttons.push(button);
+        }
+    }
+
+    /// Handles buy and sell clicks.
+    pub fn handle_ui_message(
+        &mut self,
+        message: &UiMessage,
+        inventory: &mut Inventory,
+        catalogue: &ShopCatalogue,
+        registry: &FishRegistry,
+        ui: &mut UserInterface,
+    ) -> Option<ShopAction> {
+        if !self.open {
+            return None;
+        }
+        let Some(ButtonMessage::Click) = message.data() else {
+            return None;
+        };
+        let destination = message.destination();
+        if destination == self.close {
+            return Some(ShopAction::Close);
+        }
+
+        let mut action = None;
+        if let Some(row) = self.buy_rows.iter().find(|row| row.buy == destination) {
+            let status = match inventory.buy(catalogue, &row.item_id) {
+                Ok(()) => {
+                    action = Some(ShopAction::Bought(row.item_id.clone()));
+                    "Thanks for your purchase!"
+                }
+                Err(ShopError::NotEnoughMoney) => "You can't afford that.",
+                Err(ShopError::AlreadyOwned) => "You already own that.",
+                Err(ShopError::UnknownItem) => "That item is not for sale.",
+            };
+            self.set_status(status, ui);
+            self.refresh(inventory, registry, ui);
+        } else if let Some(index) = self.sell_buttons.iter().position(|b| *b == destination) {
+            if let Some(price) = inventory.sell_fish(registry, index) {
+                self.set_status(&format!("Sold for {price}g."), ui);
+                action = Some(ShopAction::Sold(price));
+            } else {
+                self.set_status("I don't know what that fish is worth.", ui);
+            }
+            self.refresh(inventory, registry, ui);
+        }
+        action
+    }
+}
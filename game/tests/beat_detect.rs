@@ -0,0 +1,87 @@
+//! Beat detection on synthetic click tracks with a known tempo and first beat.
+use fishing_hero::{
+    beat_detect::{analyse, to_chart, Audio},
+    lanes::LaneLayout,
+};
+use std::f32::consts::PI;
+
+const SAMPLE_RATE: u32 = 44100;
+
+//a short decaying click on every beat, alternating a low and a high one like a kick and a
+//snare. every click has a burst of noise in it, as drums do
+fn click_track(bpm: f32, first_beat: f32, duration: f32) -> Audio {
+    let beat = 60.0 / bpm;
+    let mut noise = 1u64;
+    let samples = (0..(SAMPLE_RATE as f32 * duration) as usize)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            noise = noise
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let white = (noise >> 40) as f32 / (1u64 << 23) as f32 - 1.0;
+            if t < first_beat {
+                return 0.0;
+            }
+            let index = ((t - first_beat) / beat).floor();
+            let since = t - first_beat - index * beat;
+            if since >= 0.03 {
+                return 0.0;
+            }
+            let frequency = if index as i64 % 2 == 0 { 120.0 } else { 2500.0 };
+            let tone = 0.6 * (2.0 * PI * frequency * since).sin();
+            (tone + 0.3 * white) * (-since * 100.0).exp()
+        })
+        .collect();
+    Audio {
+        sample_rate: SAMPLE_RATE,
+        samples,
+    }
+}
+
+//distance from `time` to the closest beat of the grid, in seconds
+fn off_beat(time: f32, bpm: f32, first_beat: f32) -> f32 {
+    let beat = 60.0 / bpm;
+    let position = (time - first_beat) / beat;
+    (position - position.round()).abs() * beat
+}
+
+#[test]
+fn finds_the_tempo_and_first_beat() {
+    for (bpm, first_beat) in [(96.0, 0.37), (128.0, 0.1), (150.0, 0.25)] {
+        let analysis = analyse(&click_track(bpm, first_beat, 20.0));
+        assert!(
+            (analysis.bpm - bpm).abs() < 0.5,
+            "expected {bpm} bpm, found {}",
+            analysis.bpm
+        );
+        assert!(
+            off_beat(analysis.first_beat, bpm, first_beat) < 0.015,
+            "expected the first beat at {first_beat}s for {bpm} bpm, found {}s",
+            analysis.first_beat
+        );
+        assert!((analysis.duration - 20.0).abs() < 0.01);
+    }
+}
+
+#[test]
+fn charts_put_notes_on_the_beats() {
+    let (bpm, first_beat) = (120.0, 0.3);
+    let analysis = analyse(&click_track(bpm, first_beat, 20.0));
+    let chart = to_chart(&analysis, LaneLayout::Four, 0.5);
+
+    assert_eq!(chart.layout, LaneLayout::Four);
+    assert!((chart.bpm - bpm).abs() < 0.5);
+    //half a note per beat over 20 seconds at 2 beats a second
+    assert_eq!(chart.notes.len(), 20);
+    for note in &chart.notes {
+        assert!(
+            off_beat(note.time, bpm, first_beat) < 0.015,
+            "note at {}s is off the beat",
+            note.time
+        );
+        assert_eq!(note.hold, 0.0);
+    }
+    //low and high clicks end up in different lanes
+    let lanes: std::collections::HashSet<_> = chart.notes.iter().map(|note| note.lane).collect();
+    assert!(lanes.len() > 1);
+}
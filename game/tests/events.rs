@@ -0,0 +1,74 @@
+//! Publishing and subscribing on the event bus, no engine needed.
+use fishing_hero::events::{EventBus, GameEvent};
+
+fn paused(paused: bool) -> GameEvent {
+    GameEvent::PauseChanged { paused }
+}
+
+#[test]
+fn events_are_published_a_frame_at_a_time() {
+    let mut bus = EventBus::default();
+    bus.emit(paused(true));
+    assert!(bus.published().is_empty());
+
+    bus.publish();
+    bus.emit(paused(false));
+    assert_eq!(bus.published(), [paused(true)]);
+
+    bus.publish();
+    assert_eq!(bus.published(), [paused(false)]);
+    bus.publish();
+    assert!(bus.published().is_empty());
+}
+
+#[test]
+fn subscribers_read_every_event_once() {
+    let mut bus = EventBus::default();
+    bus.emit(paused(true));
+    bus.publish();
+
+    //only what is published after subscribing
+    let subscription = bus.subscribe();
+    assert!(bus.read(subscription).is_empty());
+
+    //reads may skip frames without losing anything
+    bus.emit(paused(false));
+    bus.publish();
+    bus.emit(paused(true));
+    bus.publish();
+    bus.publish();
+    assert_eq!(bus.read(subscription), [paused(false), paused(true)]);
+    assert!(bus.read(subscription).is_empty());
+}
+
+#[test]
+fn subscribers_read_at_their_own_pace() {
+    let mut bus = EventBus::default();
+    let early = bus.subscribe();
+    bus.emit(paused(true));
+    bus.publish();
+    let late = bus.subscribe();
+    assert_eq!(bus.read(early), [paused(true)]);
+
+    bus.emit(paused(false));
+    bus.publish();
+    assert_eq!(bus.read(late), [paused(false)]);
+    assert_eq!(bus.read(early), [paused(false)]);
+}
+
+#[test]
+fn unsubscribed_readers_get_nothing() {
+    let mut bus = EventBus::default();
+    let first = bus.subscribe();
+    bus.emit(paused(true));
+    bus.publish();
+    bus.unsubscribe(first);
+    assert!(bus.read(first).is_empty());
+
+    //the freed slot is handed out again and starts from scratch
+    let second = bus.subscribe();
+    assert!(bus.read(second).is_empty());
+    bus.emit(paused(false));
+    bus.publish();
+    assert_eq!(bus.read(second), [paused(false)]);
+}
@@ -0,0 +1,286 @@
+//! End-to-end runs of the real scenes and scripts, driven by synthetic key presses.
+//!
+//! Runs without the default test harness and needs a display server, see [`harness`]. Set
+//! `FISHING_HERO_SKIP_GAMEPLAY` to skip these on machines without one.
+mod harness;
+
+use fishing_hero::{
+    camera::{Camera, Projection},
+    events::GameEvent,
+    judge::Judgement,
+    CATCHING_SCENE, OVERWORLD_SCENE,
+};
+use fyrox::{
+    core::pool::Handle,
+    event_loop::EventLoopWindowTarget,
+    keyboard::KeyCode,
+    scene::{
+        camera::{Camera as EngineCamera, Projection as EngineProjection},
+        node::Node,
+    },
+};
+use harness::{Harness, TICK};
+
+//lets rigid bodies settle onto the ground before anything is measured
+const SETTLE_TICKS: usize = 10;
+//a generated chart is 45 seconds long, give it some room
+const CATCH_TICKS: usize = 60 * 60;
+
+fn standing_still_stays_put(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, OVERWORLD_SCENE);
+    harness.run(SETTLE_TICKS);
+    let start = harness
+        .player_position()
+        .expect("the overworld has no player");
+    harness.run(60);
+    let end = harness.player_position().unwrap();
+    assert!(
+        (end - start).norm() < 1e-3,
+        "moved from {start:?} to {end:?}"
+    );
+}
+
+fn walking_right_moves_the_player(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, OVERWORLD_SCENE);
+    harness.run(SETTLE_TICKS);
+    let start = harness
+        .player_position()
+        .expect("the overworld has no player");
+
+    harness.key_down(KeyCode::KeyD);
+    harness.run(60);
+    harness.key_up(KeyCode::KeyD);
+    let end = harness.player_position().unwrap();
+
+    //the 2d camera looks down +z, which puts +x on the left of the screen
+    let walked = start.x - end.x;
+    let expected = 3.0 * 60.0 * TICK;
+    assert!(
+        (walked - expected).abs() < 0.25,
+        "walked {walked} to the right, expected about {expected}"
+    );
+    assert!((end.y - start.y).abs() < 1e-3);
+
+    //and stops once the key is let go
+    harness.run(SETTLE_TICKS);
+    let stopped = harness.player_position().unwrap();
+    harness.run(30);
+    assert!((harness.player_position().unwrap() - stopped).norm() < 1e-3);
+}
+
+fn walking_up_moves_the_player(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, OVERWORLD_SCENE);
+    harness.run(SETTLE_TICKS);
+    let start = harness
+        .player_position()
+        .expect("the overworld has no player");
+
+    harness.key_down(KeyCode::KeyW);
+    harness.run(30);
+    let end = harness.player_position().unwrap();
+    assert!(end.y > start.y, "moved from {start:?} to {end:?}");
+}
+
+fn camera_follows_player(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, OVERWORLD_SCENE);
+    harness.run(SETTLE_TICKS);
+    let camera = harness
+        .follow_camera()
+        .expect("the overworld camera does not follow the player");
+    let start = harness.node_position(camera).unwrap();
+    let player = harness.player_position().unwrap();
+    assert!(
+        (start.xy() - player.xy()).norm() < 1e-3,
+        "the camera at {start:?} did not start on the player at {player:?}"
+    );
+
+    //walks well past the dead zone
+    harness.key_down(KeyCode::KeyD);
+    harness.run(120);
+    let walking = harness.node_position(camera).unwrap();
+    let player = harness.player_position().unwrap();
+    assert!(
+        start.x - walking.x > 3.0,
+        "moved from {start:?} to {walking:?}"
+    );
+    assert!((walking.y - start.y).abs() < 1e-3);
+    //with the dead zone and the look-ahead the player is off centre, but never far
+    assert!((walking.x - player.x).abs() < 3.0);
+}
+
+//the game's own camera is drawn through an engine camera that goes wherever it goes
+fn custom_camera_follows_player(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, OVERWORLD_SCENE);
+    harness.run(SETTLE_TICKS);
+    let camera = harness
+        .follow_with_custom_camera()
+        .expect("the overworld has no camera");
+    harness.run(2);
+    let render_camera = render_camera_of(&harness, camera);
+    let start = harness.node_position(camera).unwrap();
+    let player = harness.player_position().unwrap();
+    assert!(
+        (start.xy() - player.xy()).norm() < 1e-3,
+        "the camera at {start:?} did not start on the player at {player:?}"
+    );
+
+    harness.key_down(KeyCode::KeyD);
+    harness.run(120);
+    let walking = harness.node_position(camera).unwrap();
+    assert!(
+        start.x - walking.x > 3.0,
+        "moved from {start:?} to {walking:?}"
+    );
+    let rendered = harness.node_position(render_camera).unwrap();
+    assert!(
+        (rendered - walking).norm() < 1e-3,
+        "drawn from {rendered:?} while the camera is at {walking:?}"
+    );
+}
+
+//the one enabled engine camera of the scene, the child of `camera` with its projection
+fn render_camera_of(harness: &Harness, camera: Handle<Node>) -> Handle<Node> {
+    let graph = &harness.scene().unwrap().graph;
+    let custom = graph[camera]
+        .cast::<Camera>()
+        .expect("not one of the game's cameras");
+    let render_camera = custom.render_camera();
+    assert_eq!(graph[render_camera].parent(), camera);
+    let engine = graph[render_camera]
+        .cast::<EngineCamera>()
+        .expect("no engine camera to draw through");
+    assert!(engine.is_enabled());
+    match (custom.projection(), engine.projection()) {
+        (Projection::Orthographic(custom), EngineProjection::Orthographic(engine)) => {
+            assert_eq!(custom.vertical_size, engine.vertical_size)
+        }
+        projections => panic!("projections differ: {projections:?}"),
+    }
+    let engine_cameras = graph
+        .pair_iter()
+        .filter(|(_, node)| node.cast::<EngineCamera>().is_some_and(|c| c.is_enabled()))
+        .count();
+    assert_eq!(engine_cameras, 1);
+    render_camera
+}
+
+fn perfect_input_wins(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, CATCHING_SCENE);
+    assert!(!harness.game().chart().notes.is_empty());
+    harness.play_perfectly(CATCH_TICKS);
+
+    let result = harness
+        .game()
+        .last_result()
+        .expect("the catch never finished");
+    assert!(result.won, "lost with {:?}", result.stats);
+    assert_eq!(result.stats.misses, 0);
+    assert_eq!(result.stats.greats, result.stats.total());
+
+    let events = harness.events();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, GameEvent::CatchStarted { .. })));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, GameEvent::CatchEnded { won: true, .. })));
+    assert!(!events.iter().any(|event| matches!(
+        event,
+        GameEvent::Judged {
+            judgement: Judgement::Miss,
+            ..
+        }
+    )));
+}
+
+fn no_input_loses(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, CATCHING_SCENE);
+    let finished = harness.run_until(CATCH_TICKS, |harness| {
+        harness.game().last_result().is_some()
+    });
+    assert!(finished, "the catch never finished");
+
+    let result = harness.game().last_result().unwrap();
+    assert!(!result.won);
+    assert_eq!(result.stats.total(), result.stats.misses);
+
+    //every miss names its own note of the chart
+    let notes = harness.game().chart().notes.len();
+    let mut missed: Vec<_> = harness
+        .events()
+        .iter()
+        .filter_map(|event| match event {
+            GameEvent::Judged { note, .. } => Some(*note),
+            _ => None,
+        })
+        .collect();
+    assert!(missed.len() as u32 >= result.stats.total());
+    assert!(missed.iter().all(|note| *note < notes), "{missed:?}");
+    let judged = missed.len();
+    missed.sort();
+    missed.dedup();
+    assert_eq!(missed.len(), judged, "a note was judged twice");
+}
+
+fn notes_are_pooled(window_target: &EventLoopWindowTarget<()>) {
+    let mut harness = Harness::new(window_target, CATCHING_SCENE);
+    let notes = harness.game().chart().notes.len();
+    let blocks = harness.game().note_pool().block_count();
+    let nodes = harness.scene().unwrap().graph.node_count();
+
+    //autoplay gets through the whole chart, so every note comes into view once
+    harness.game_mut().set_autoplay(true);
+    let mut most = nodes;
+    let finished = harness.run_until(CATCH_TICKS, |harness| {
+        most = most.max(harness.scene().unwrap().graph.node_count());
+        harness.game().last_result().is_some()
+    });
+    assert!(finished, "the catch never finished");
+
+    //the scene only grows when the pool has to, and never to the size of the chart
+    let grown = harness.game().note_pool().block_count() - blocks;
+    assert_eq!((most - nodes) as usize, grown);
+    assert!(
+        grown == 0 || blocks + grown < notes,
+        "{grown} blocks for {notes} notes"
+    );
+}
+
+fn main() {
+    let scenarios: [(&str, fn(&EventLoopWindowTarget<()>)); 8] = [
+        ("standing_still_stays_put", standing_still_stays_put),
+        (
+            "walking_right_moves_the_player",
+            walking_right_moves_the_player,
+        ),
+        ("walking_up_moves_the_player", walking_up_moves_the_player),
+        ("camera_follows_player", camera_follows_player),
+        ("custom_camera_follows_player", custom_camera_follows_player),
+        ("perfect_input_wins", perfect_input_wins),
+        ("no_input_loses", no_input_loses),
+        ("notes_are_pooled", notes_are_pooled),
+    ];
+
+    //`cargo test -- name` runs only the scenarios whose name contains `name`
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    //headless machines without Xvfb cannot run the engine at all, they have to ask to skip
+    let Some(event_loop) = harness::event_loop() else {
+        println!(
+            "Skipped all {} gameplay scenarios, {} is set.",
+            scenarios.len(),
+            harness::SKIP_VAR
+        );
+        return;
+    };
+    for (name, scenario) in scenarios {
+        if filter
+            .as_ref()
+            .is_some_and(|filter| !name.contains(filter.as_str()))
+        {
+            continue;
+        }
+        println!("test {name} ...");
+        scenario(&event_loop);
+        println!("test {name} ... ok");
+    }
+}
@@ -0,0 +1,277 @@
+//! Runs the game tick by tick without opening a window, with keys pressed by the test.
+//!
+//! The engine never creates a graphics context, so scenes load and scripts and physics run,
+//! but nothing is rendered and no GPU is needed. winit still has to connect to a display
+//! server (or Xvfb) to build the event loop; where there is none the run fails, unless
+//! [`SKIP_VAR`] is set to skip the scenarios on purpose.
+//! Keys are handed straight to the scripts and the plugin, winit does not allow building
+//! keyboard events outside of itself.
+//!
+//! winit only allows one event loop per process and it has to live on the main thread, so
+//! tests using the harness run from a `main` of their own instead of the default harness.
+use fishing_hero::{
+    camera::{CameraBuilder, OrthographicProjection, Projection},
+    events::GameEvent,
+    follow_camera::FollowCamera,
+    player::Player,
+    Game, GameConstructor,
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{algebra::Vector3, pool::Handle, task::TaskPool},
+    engine::{Engine, EngineInitParams, GraphicsContextParams, SerializationContext},
+    event_loop::{EventLoop, EventLoopWindowTarget},
+    gui::constructor::WidgetConstructorContainer,
+    keyboard::KeyCode,
+    scene::{base::BaseBuilder, camera::Camera, node::Node, transform::TransformBuilder, Scene},
+    script::Script,
+};
+use std::sync::Arc;
+
+/// Length of one tick, the game's fixed update rate.
+pub const TICK: f32 = 1.0 / 60.0;
+/// Ticks allowed for a scene to load before the test gives up.
+const LOAD_TIMEOUT: usize = 600;
+
+/// Set to anything to skip the scenarios on machines without a display server.
+pub const SKIP_VAR: &str = "FISHING_HERO_SKIP_GAMEPLAY";
+
+/// Creates the one event loop of the test run. It is never run, it only lets the engine
+/// talk to the windowing system. Returns `None` when there is no display server to talk to
+/// and [`SKIP_VAR`] is set, panics when it is not.
+pub fn event_loop() -> Option<EventLoop<()>> {
+    match EventLoop::new() {
+        Ok(event_loop) => Some(event_loop),
+        Err(e) if std::env::var_os(SKIP_VAR).is_some() => {
+            println!("Unable to connect to a display server: {e}");
+            None
+        }
+        Err(e) => panic!(
+            "unable to connect to a display server: {e}. Run under Xvfb, or set {SKIP_VAR} \
+             to skip the gameplay scenarios"
+        ),
+    }
+}
+
+pub struct Harness<'a> {
+    engine: Engine,
+    window_target: &'a EventLoopWindowTarget<()>,
+    lag: f32,
+    //every event the game published so far
+    events: Vec<GameEvent>,
+}
+
+impl<'a> Harness<'a> {
+    /// Starts the game on `scene_path`, relative to the repository root, and ticks until it
+    /// has loaded.
+    pub fn new(window_target: &'a EventLoopWindowTarget<()>, scene_path: &str) -> Self {
+        //data paths in scenes and registries are relative to the repository root
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+
+        let task_pool = Arc::new(TaskPool::new());
+        let mut engine = Engine::new(EngineInitParams {
+            graphics_context_params: GraphicsContextParams {
+                window_attributes: Default::default(),
+                vsync: false,
+            },
+            resource_manager: ResourceManager::new(task_pool.clone()),
+            serialization_context: Arc::new(SerializationContext::new()),
+            widget_constructors: Arc::new(WidgetConstructorContainer::new()),
+            task_pool,
+        })
+        .unwrap();
+        engine.add_plugin_constructor(GameConstructor);
+        engine.enable_plugins(Some(scene_path), true, Some(window_target));
+
+        let mut harness = Self {
+            engine,
+            window_target,
+            lag: 0.0,
+            events: Vec::new(),
+        };
+        let loaded = harness.run_until(LOAD_TIMEOUT, |harness| harness.scene().is_some());
+        assert!(
+            loaded,
+            "{scene_path} did not load within {LOAD_TIMEOUT} ticks"
+        );
+        harness
+    }
+
+    /// Advances the game by one fixed tick.
+    pub fn tick(&mut self) {
+        self.lag += TICK;
+        while self.lag >= TICK {
+            self.lag -= TICK;
+            self.engine
+                .update(TICK, self.window_target, &mut self.lag, Default::default());
+            let published = self.game().events().to_vec();
+            self.events.extend(published);
+        }
+    }
+
+    /// Every event published since the harness started, in order.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn run(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Ticks until `done` holds, at most `ticks` times. Returns whether it ever held.
+    pub fn run_until(&mut self, ticks: usize, mut done: impl FnMut(&mut Self) -> bool) -> bool {
+        for _ in 0..ticks {
+            if done(self) {
+                return true;
+            }
+            self.tick();
+        }
+        done(self)
+    }
+
+    pub fn game(&self) -> &Game {
+        self.engine
+            .plugins()
+            .iter()
+            .find_map(|plugin| plugin.cast::<Game>())
+            .expect("the game plugin is not running")
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        self.engine
+            .plugins_mut()
+            .iter_mut()
+            .find_map(|plugin| plugin.cast_mut::<Game>())
+            .expect("the game plugin is not running")
+    }
+
+    /// The loaded scene, the one holding the music is left out.
+    pub fn scene(&self) -> Option<&Scene> {
+        self.engine.scenes.try_get(self.game().scene())
+    }
+
+    fn scene_mut(&mut self) -> Option<&mut Scene> {
+        let scene = self.game().scene();
+        self.engine.scenes.try_get_mut(scene)
+    }
+
+    /// Presses a key, as if it went down right before the next tick.
+    pub fn key_down(&mut self, key: KeyCode) {
+        self.key(key, true);
+    }
+
+    pub fn key_up(&mut self, key: KeyCode) {
+        self.key(key, false);
+    }
+
+    //movement keys go to every player script, lane keys to the catch
+    fn key(&mut self, key: KeyCode, pressed: bool) {
+        if let Some(scene) = self.scene_mut() {
+            let players: Vec<_> = scene
+                .graph
+                .pair_iter()
+                .filter(|(_, node)| node.try_get_script::<Player>().is_some())
+                .map(|(handle, _)| handle)
+                .collect();
+            for handle in players {
+                if let Some(player) = scene.graph[handle].try_get_script_mut::<Player>() {
+                    player.handle_key(key, pressed);
+                }
+            }
+        }
+        self.game_mut().lane_key(key, pressed);
+    }
+
+    /// World position of the player, if the scene has one.
+    pub fn player_position(&self) -> Option<Vector3<f32>> {
+        let scene = self.scene()?;
+        let player = fishing_hero::player::find_player(&scene.graph);
+        player
+            .is_some()
+            .then(|| scene.graph[player].global_position())
+    }
+
+    /// The scene's camera, if it follows the player with a follow camera script.
+    pub fn follow_camera(&self) -> Option<Handle<Node>> {
+        let scene = self.scene()?;
+        scene
+            .graph
+            .pair_iter()
+            .find(|(_, node)| {
+                node.cast::<Camera>().is_some() && node.try_get_script::<FollowCamera>().is_some()
+            })
+            .map(|(handle, _)| handle)
+    }
+
+    /// Swaps the scene's camera for one of the game's own, with a follow camera script with
+    /// default settings, and returns it. It starts on the next tick.
+    pub fn follow_with_custom_camera(&mut self) -> Option<Handle<Node>> {
+        let scene = self.scene_mut()?;
+        let stock = scene
+            .graph
+            .pair_iter()
+            .find(|(_, node)| node.cast::<Camera>().is_some())
+            .map(|(handle, _)| handle)?;
+        let position = scene.graph[stock].global_position();
+        scene.graph.remove_node(stock);
+        let camera = CameraBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(position)
+                        .build(),
+                )
+                .with_script(Script::new(FollowCamera::default())),
+        )
+        .with_projection(Projection::Orthographic(OrthographicProjection::default()))
+        .build(&mut scene.graph);
+        Some(camera)
+    }
+
+    /// World position of a node of the scene.
+    pub fn node_position(&self, node: Handle<Node>) -> Option<Vector3<f32>> {
+        let scene = self.scene()?;
+        scene.graph.try_get(node).map(|node| node.global_position())
+    }
+
+    /// Presses every note of the current chart at the first tick on or after its ideal
+    /// moment and lets go on the first tick after it ends, until the catch is over or
+    /// `ticks` ran out. Taps are let go of on the next tick.
+    pub fn play_perfectly(&mut self, ticks: usize) {
+        let mut next = 0;
+        //keys that are down and the chart time at which they are let go of
+        let mut held: Vec<(KeyCode, f32)> = Vec::new();
+        for _ in 0..ticks {
+            if self.game().last_result().is_some() {
+                return;
+            }
+
+            let game = self.game();
+            let now = game.song_time() - game.audio_offset();
+            let layout = game.lane_layout();
+            let due: Vec<_> = game.chart().notes[next..]
+                .iter()
+                .take_while(|note| note.time <= now)
+                .filter_map(|note| Some((layout.key(note.lane)?, note.time + note.hold)))
+                .collect();
+            next += game.chart().notes[next..]
+                .iter()
+                .take_while(|note| note.time <= now)
+                .count();
+
+            let (done, still_held): (Vec<_>, Vec<_>) =
+                held.into_iter().partition(|(_, end)| *end < now);
+            held = still_held;
+            for (key, _) in done {
+                self.key_up(key);
+            }
+            for (key, end) in due {
+                self.key_down(key);
+                held.push((key, end));
+            }
+            self.tick();
+        }
+    }
+}
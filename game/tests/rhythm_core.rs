@@ -0,0 +1,603 @@
+//! Judging, scoring and whole catches driven by song times and lane inputs, no engine needed.
+use fishing_hero::{
+    chart::{Chart, ChartNote},
+    chart_gen::{self, GeneratorParams},
+    judge::{HitWindows, Judgement, NoteJudge},
+    lanes::{Lane, LaneLayout},
+    replay::{chart_checksum, InputEvent},
+    scoring::{progress_delta, CatchStats, Grade, Outcome, Progress, START_PROGRESS},
+    session::CatchSession,
+};
+use proptest::prelude::*;
+
+fn tap(time: f32, lane: Lane) -> ChartNote {
+    ChartNote {
+        time,
+        lane,
+        hold: 0.0,
+    }
+}
+
+fn chart(notes: Vec<ChartNote>) -> Chart {
+    let mut chart = Chart {
+        notes,
+        ..Default::default()
+    };
+    chart.sort();
+    chart
+}
+
+fn press(time: f32, lane: Lane) -> InputEvent {
+    InputEvent {
+        time,
+        lane,
+        pressed: true,
+    }
+}
+
+//a press on every note exactly when it should be hit
+fn perfect_inputs(chart: &Chart, audio_offset: f32) -> Vec<InputEvent> {
+    chart
+        .notes
+        .iter()
+        .map(|note| press(note.time + audio_offset, note.lane))
+        .collect()
+}
+
+fn session(chart: Chart) -> CatchSession {
+    CatchSession::new(chart, HitWindows::default(), 0.0, Progress::default())
+}
+
+fn generated(seed: u64, layout: LaneLayout, density: f32) -> Chart {
+    chart_gen::generate(&GeneratorParams {
+        seed,
+        layout,
+        density,
+        duration: 20.0,
+        ..Default::default()
+    })
+}
+
+fn any_judgement() -> impl Strategy<Value = Judgement> {
+    prop_oneof![
+        Just(Judgement::Great),
+        Just(Judgement::Good),
+        Just(Judgement::Ok),
+        Just(Judgement::Miss),
+    ]
+}
+
+fn any_layout() -> impl Strategy<Value = LaneLayout> {
+    prop_oneof![
+        Just(LaneLayout::Four),
+        Just(LaneLayout::Five),
+        Just(LaneLayout::Six),
+    ]
+}
+
+fn any_lane() -> impl Strategy<Value = Lane> {
+    (0..Lane::ALL.len()).prop_map(|index| Lane::ALL[index])
+}
+
+#[test]
+fn windows_judge_by_distance() {
+    let windows = HitWindows::default();
+    assert_eq!(windows.judge(0.0), Some(Judgement::Great));
+    assert_eq!(windows.judge(windows.great), Some(Judgement::Great));
+    assert_eq!(windows.judge(-windows.good), Some(Judgement::Good));
+    assert_eq!(windows.judge(windows.ok), Some(Judgement::Ok));
+    assert_eq!(windows.judge(windows.ok + 0.01), None);
+}
+
+#[test]
+fn judge_picks_the_closest_note_in_the_lane() {
+    let mut judge = NoteJudge::default();
+    judge.track(0, Lane::One, 1.0);
+    judge.track(1, Lane::One, 1.15);
+    judge.track(2, Lane::Two, 1.1);
+
+    assert_eq!(judge.press(Lane::One, 1.12), Some(Judgement::Great));
+    assert_eq!(judge.take_result(1), Some(Judgement::Great));
+    assert_eq!(judge.take_result(0), None);
+}
+
+#[test]
+fn judge_breaks_ties_by_time_then_tracking_order() {
+    //keys are tracked out of order so the map order cannot stand in for the tie break
+    let mut judge = NoteJudge::default();
+    judge.track(0, Lane::One, 1.25);
+    judge.track(1, Lane::One, 1.0);
+    assert_eq!(judge.press(Lane::One, 1.125), Some(Judgement::Ok));
+    assert_eq!(judge.take_result(1), Some(Judgement::Ok));
+
+    let mut judge = NoteJudge::default();
+    for key in [7, 3, 5] {
+        judge.track(key, Lane::One, 1.0);
+    }
+    let order: Vec<_> = (0..3)
+        .map(|_| {
+            judge.press(Lane::One, 1.0);
+            judge.take_judged()[0].note
+        })
+        .collect();
+    assert_eq!(order, vec![7, 3, 5]);
+}
+
+#[test]
+fn judge_ignores_presses_out_of_reach() {
+    let mut judge = NoteJudge::default();
+    judge.track(0, Lane::One, 1.0);
+    assert_eq!(judge.press(Lane::One, 0.5), None);
+    assert_eq!(judge.press(Lane::Two, 1.0), None);
+    assert!(judge.take_judged().is_empty());
+}
+
+#[test]
+fn judge_never_judges_a_note_twice() {
+    let mut judge = NoteJudge::default();
+    judge.track(0, Lane::One, 1.0);
+    assert_eq!(judge.press(Lane::One, 1.0), Some(Judgement::Great));
+    assert_eq!(judge.press(Lane::One, 1.01), None);
+    judge.expire(10.0);
+    assert_eq!(judge.take_judged().len(), 1);
+}
+
+#[test]
+fn judge_expires_notes_in_time_order() {
+    let mut judge = NoteJudge::default();
+    for (key, time) in [(0, 3.0), (1, 1.0), (2, 2.0), (3, 9.0)] {
+        judge.track(key, Lane::One, time);
+    }
+    judge.expire(5.0);
+
+    let missed: Vec<_> = judge
+        .take_judged()
+        .iter()
+        .map(|judged| judged.note)
+        .collect();
+    assert_eq!(missed, vec![1, 2, 0]);
+    assert_eq!(judge.take_result(3), None);
+}
+
+#[test]
+fn judge_expires_only_past_the_ok_window() {
+    //values that add up exactly in floating point
+    let mut judge = NoteJudge::default();
+    judge.set_windows(HitWindows {
+        great: 0.0625,
+        good: 0.125,
+        ok: 0.25,
+    });
+    judge.track(0, Lane::One, 1.0);
+    judge.expire(1.25);
+    assert_eq!(judge.take_result(0), None);
+    judge.expire(1.251);
+    assert_eq!(judge.take_result(0), Some(Judgement::Miss));
+}
+
+#[test]
+fn holds_are_judged_when_they_end() {
+    let mut judge = NoteJudge::default();
+    judge.track_hold(0, Lane::One, 1.0, 2.0);
+    assert_eq!(judge.press(Lane::One, 1.07), Some(Judgement::Good));
+    judge.expire(2.5);
+    assert!(judge.take_judged().is_empty());
+
+    //held to the end, the head decides
+    judge.expire(3.0);
+    assert_eq!(judge.take_result(0), Some(Judgement::Good));
+    assert_eq!(judge.release(Lane::One, 3.1), None);
+}
+
+#[test]
+fn letting_go_of_a_hold_early_misses_it() {
+    let mut judge = NoteJudge::default();
+    judge.track_hold(0, Lane::One, 1.0, 2.0);
+    judge.press(Lane::One, 1.0);
+    assert_eq!(judge.release(Lane::Two, 2.0), None);
+    assert_eq!(judge.release(Lane::One, 2.0), Some(Judgement::Miss));
+    assert_eq!(judge.take_result(0), Some(Judgement::Miss));
+}
+
+#[test]
+fn holds_may_be_let_go_of_inside_the_ok_window() {
+    let mut judge = NoteJudge::default();
+    let ok = judge.windows().ok;
+    judge.track_hold(0, Lane::One, 1.0, 2.0);
+    judge.press(Lane::One, 1.0);
+    assert_eq!(judge.release(Lane::One, 3.0 - ok), Some(Judgement::Great));
+}
+
+#[test]
+fn holds_never_pressed_expire_like_taps() {
+    let mut judge = NoteJudge::default();
+    judge.track_hold(0, Lane::One, 1.0, 2.0);
+    judge.expire(1.5);
+    assert_eq!(judge.take_result(0), Some(Judgement::Miss));
+    assert_eq!(judge.press(Lane::One, 2.0), None);
+}
+
+#[test]
+fn clearing_forgets_everything() {
+    let mut judge = NoteJudge::default();
+    judge.track(0, Lane::One, 1.0);
+    judge.press(Lane::One, 1.0);
+    judge.clear();
+    assert!(judge.take_judged().is_empty());
+    assert_eq!(judge.take_result(0), None);
+}
+
+#[test]
+fn stats_count_combos() {
+    let mut stats = CatchStats::default();
+    for judgement in [
+        Judgement::Great,
+        Judgement::Good,
+        Judgement::Ok,
+        Judgement::Miss,
+        Judgement::Great,
+    ] {
+        stats.add(judgement);
+    }
+    assert_eq!(stats.total(), 5);
+    assert_eq!(stats.combo, 1);
+    assert_eq!(stats.max_combo, 3);
+}
+
+#[test]
+fn accuracy_weighs_judgements() {
+    let mut stats = CatchStats::default();
+    assert_eq!(stats.accuracy(), 0.0);
+    stats.add(Judgement::Great);
+    stats.add(Judgement::Ok);
+    assert!((stats.accuracy() - 2.0 / 3.0).abs() < 1e-6);
+    assert_eq!(stats.grade(), Grade::C);
+}
+
+#[test]
+fn grades_follow_accuracy() {
+    assert_eq!(Grade::from_accuracy(1.0), Grade::S);
+    assert_eq!(Grade::from_accuracy(0.9), Grade::A);
+    assert_eq!(Grade::from_accuracy(0.7), Grade::B);
+    assert_eq!(Grade::from_accuracy(0.6), Grade::C);
+    assert_eq!(Grade::from_accuracy(0.0), Grade::D);
+}
+
+#[test]
+fn progress_wins_at_the_goal() {
+    let mut progress = Progress::new(START_PROGRESS + 8.0);
+    assert_eq!(progress.apply(Judgement::Great), None);
+    assert_eq!(progress.apply(Judgement::Great), Some(Outcome::Won));
+    //decided catches stay decided
+    assert_eq!(progress.apply(Judgement::Miss), None);
+    assert_eq!(progress.outcome(), Some(Outcome::Won));
+}
+
+#[test]
+fn progress_loses_when_empty() {
+    let mut progress = Progress::default();
+    let misses = (START_PROGRESS / -progress_delta(Judgement::Miss)) as usize;
+    for _ in 1..misses {
+        assert_eq!(progress.apply(Judgement::Miss), None);
+    }
+    assert_eq!(progress.apply(Judgement::Miss), Some(Outcome::Lost));
+}
+
+#[test]
+fn no_fail_bottoms_out() {
+    let mut progress = Progress::default();
+    progress.set_no_fail(true);
+    for _ in 0..100 {
+        assert_eq!(progress.apply(Judgement::Miss), None);
+    }
+    assert_eq!(progress.value(), 0.0);
+    assert_eq!(progress.apply(Judgement::Great), None);
+    assert_eq!(progress.value(), progress_delta(Judgement::Great));
+}
+
+#[test]
+fn perfect_play_wins_with_an_s() {
+    let chart = generated(7, LaneLayout::Four, 1.0);
+    let inputs = perfect_inputs(&chart, 0.0);
+    let mut session = session(chart);
+    session.run(&inputs);
+
+    assert!(session.is_complete());
+    assert_eq!(session.outcome(), Some(Outcome::Won));
+    assert_eq!(session.stats().greats, session.stats().total());
+    assert_eq!(session.stats().grade(), Grade::S);
+}
+
+#[test]
+fn no_input_loses() {
+    let chart = generated(7, LaneLayout::Four, 1.0);
+    let count = chart.notes.len() as u32;
+    let mut session = session(chart);
+    session.run(&[]);
+
+    assert_eq!(session.outcome(), Some(Outcome::Lost));
+    assert_eq!(session.stats().misses, count);
+}
+
+#[test]
+fn audio_offset_moves_the_ideal_moment() {
+    let chart = chart(vec![tap(1.0, Lane::One)]);
+    let mut session = CatchSession::new(chart, HitWindows::default(), 0.08, Progress::default());
+    let judged = session.press(Lane::One, 1.08);
+    assert_eq!(judged.len(), 1);
+    assert_eq!(judged[0].judgement, Judgement::Great);
+}
+
+#[test]
+fn late_presses_miss_expired_notes_first() {
+    let chart = chart(vec![tap(1.0, Lane::One), tap(2.0, Lane::One)]);
+    let mut session = session(chart);
+    let judged = session.press(Lane::One, 2.0);
+
+    let order: Vec<_> = judged.iter().map(|j| (j.note, j.judgement)).collect();
+    assert_eq!(order, vec![(0, Judgement::Miss), (1, Judgement::Great)]);
+}
+
+#[test]
+fn chords_are_hit_lane_by_lane() {
+    let chart = chart(vec![tap(1.0, Lane::One), tap(1.0, Lane::Three)]);
+    let mut session = session(chart);
+    session.press(Lane::One, 1.0);
+    session.press(Lane::Three, 1.02);
+    assert_eq!(session.judgement(0), Some(Judgement::Great));
+    assert_eq!(session.judgement(1), Some(Judgement::Great));
+}
+
+#[test]
+fn time_never_goes_backwards() {
+    let mut session = session(chart(vec![tap(1.0, Lane::One)]));
+    session.advance(3.0);
+    session.advance(0.5);
+    assert_eq!(session.time(), 3.0);
+    assert!(session.press(Lane::One, 1.0).is_empty());
+    assert_eq!(session.judgement(0), Some(Judgement::Miss));
+}
+
+#[test]
+fn restarting_replays_the_notes_but_keeps_the_score() {
+    let notes = chart(vec![tap(1.0, Lane::One), tap(2.0, Lane::One)]);
+    let mut session = session(notes.clone());
+    session.press(Lane::One, 1.0);
+    session.advance(3.0);
+    let progress = session.progress().value();
+
+    session.restart(notes, 0.5);
+    assert_eq!(session.time(), 0.5);
+    assert_eq!(session.judgement(0), None);
+    assert!(!session.is_complete());
+    assert_eq!(session.progress().value(), progress);
+
+    session.press(Lane::One, 1.0);
+    assert_eq!(session.judgement(0), Some(Judgement::Great));
+    assert_eq!(session.stats().greats, 2);
+    assert_eq!(session.stats().misses, 1);
+}
+
+#[test]
+fn tracked_notes_are_judged_alongside_the_chart() {
+    let mut session = session(chart(vec![tap(1.0, Lane::One)]));
+    session.track(1, Lane::Two, 1.5);
+    session.press(Lane::Two, 1.5);
+    session.advance(3.0);
+
+    assert_eq!(session.judgement(0), Some(Judgement::Miss));
+    assert_eq!(session.judgement(1), Some(Judgement::Great));
+    assert_eq!(session.stats().total(), 2);
+}
+
+#[test]
+fn sessions_judge_holds_on_release() {
+    let hold = ChartNote {
+        time: 1.0,
+        lane: Lane::One,
+        hold: 1.0,
+    };
+    let release = |time| InputEvent {
+        time,
+        lane: Lane::One,
+        pressed: false,
+    };
+
+    let mut held = session(chart(vec![hold]));
+    held.run(&[press(1.0, Lane::One), release(2.0)]);
+    assert_eq!(held.judgement(0), Some(Judgement::Great));
+
+    let mut dropped = session(chart(vec![hold]));
+    let judged = dropped.run(&[press(1.0, Lane::One), release(1.5)]);
+    assert_eq!(judged.len(), 1);
+    assert_eq!(dropped.judgement(0), Some(Judgement::Miss));
+}
+
+#[test]
+fn releases_only_move_time() {
+    let mut session = session(chart(vec![tap(1.0, Lane::One)]));
+    let release = InputEvent {
+        time: 1.0,
+        lane: Lane::One,
+        pressed: false,
+    };
+    assert!(session.input(&release).is_empty());
+    assert_eq!(session.time(), 1.0);
+    assert_eq!(session.judgement(0), None);
+}
+
+#[test]
+fn chart_editing_round_trips() {
+    let mut chart = chart(vec![tap(1.0, Lane::One)]);
+    assert!(chart.toggle_note(2.0, Lane::Two, 0.01));
+    assert!(!chart.toggle_note(2.005, Lane::Two, 0.01));
+    assert_eq!(chart.notes.len(), 1);
+    assert_eq!(Chart::from_ron(&chart.to_ron().unwrap()).unwrap(), chart);
+}
+
+#[test]
+fn checksums_cover_holds_and_hit_windows() {
+    let taps = chart(vec![tap(1.0, Lane::One), tap(2.0, Lane::Two)]);
+    let windows = HitWindows::default();
+    let checksum = chart_checksum(&taps, &windows);
+    assert_eq!(chart_checksum(&taps, &windows), checksum);
+
+    let mut held = taps.clone();
+    held.notes[1].hold = 0.5;
+    assert_ne!(chart_checksum(&held, &windows), checksum);
+
+    let wider = HitWindows {
+        ok: windows.ok + 0.05,
+        ..windows
+    };
+    assert_ne!(chart_checksum(&taps, &wider), checksum);
+}
+
+proptest! {
+    #[test]
+    fn windows_are_symmetric_and_ordered(offset in -1.0f32..1.0) {
+        let windows = HitWindows::default();
+        prop_assert_eq!(windows.judge(offset), windows.judge(-offset));
+        let rank = |offset: f32| match windows.judge(offset) {
+            Some(Judgement::Great) => 0,
+            Some(Judgement::Good) => 1,
+            Some(Judgement::Ok) => 2,
+            _ => 3,
+        };
+        prop_assert!(rank(offset * 0.5) <= rank(offset));
+    }
+
+    #[test]
+    fn stats_stay_consistent(judgements in prop::collection::vec(any_judgement(), 0..200)) {
+        let mut stats = CatchStats::default();
+        for judgement in judgements.iter() {
+            stats.add(*judgement);
+        }
+        prop_assert_eq!(stats.total() as usize, judgements.len());
+        prop_assert!(stats.combo <= stats.max_combo);
+        prop_assert!(stats.max_combo <= stats.total() - stats.misses);
+        prop_assert!((0.0..=1.0).contains(&stats.accuracy()));
+        if stats.misses == 0 {
+            prop_assert_eq!(stats.combo, stats.total());
+        }
+    }
+
+    #[test]
+    fn grades_never_drop_as_accuracy_rises(a in 0.0f32..=1.0, b in 0.0f32..=1.0) {
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        prop_assert!(Grade::from_accuracy(low) <= Grade::from_accuracy(high));
+    }
+
+    #[test]
+    fn progress_is_decided_at_most_once(
+        judgements in prop::collection::vec(any_judgement(), 0..200),
+        no_fail in any::<bool>(),
+    ) {
+        let mut progress = Progress::default();
+        progress.set_no_fail(no_fail);
+        let outcomes: Vec<_> = judgements
+            .iter()
+            .filter_map(|judgement| progress.apply(*judgement))
+            .collect();
+        prop_assert!(outcomes.len() <= 1);
+        prop_assert_eq!(outcomes.first().copied(), progress.outcome());
+        if no_fail {
+            prop_assert!(progress.value() >= 0.0);
+            prop_assert_ne!(progress.outcome(), Some(Outcome::Lost));
+        }
+    }
+
+    #[test]
+    fn every_note_is_judged_exactly_once(
+        seed in any::<u64>(),
+        layout in any_layout(),
+        density in 0.25f32..3.0,
+        presses in prop::collection::vec((0.0f32..25.0, any_lane()), 0..300),
+    ) {
+        let chart = generated(seed, layout, density);
+        let count = chart.notes.len();
+        let mut inputs: Vec<_> = presses.into_iter().map(|(time, lane)| press(time, lane)).collect();
+        inputs.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut session = session(chart);
+        let judged = session.run(&inputs);
+
+        prop_assert!(session.is_complete());
+        prop_assert_eq!(judged.len(), count);
+        prop_assert_eq!(session.stats().total() as usize, count);
+        let mut notes: Vec<_> = judged.iter().map(|judged| judged.note).collect();
+        notes.sort_unstable();
+        notes.dedup();
+        prop_assert_eq!(notes.len(), count);
+    }
+
+    #[test]
+    fn sessions_are_deterministic(
+        seed in any::<u64>(),
+        presses in prop::collection::vec((0.0f32..25.0, any_lane()), 0..200),
+    ) {
+        let chart = generated(seed, LaneLayout::Six, 1.5);
+        let mut inputs: Vec<_> = presses.into_iter().map(|(time, lane)| press(time, lane)).collect();
+        inputs.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut first = session(chart.clone());
+        let mut second = session(chart);
+        prop_assert_eq!(first.run(&inputs), second.run(&inputs));
+        prop_assert_eq!(first.stats(), second.stats());
+        prop_assert_eq!(first.outcome(), second.outcome());
+    }
+
+    #[test]
+    fn perfect_play_is_all_greats(
+        seed in any::<u64>(),
+        layout in any_layout(),
+        density in 0.25f32..3.0,
+        audio_offset in -0.2f32..0.2,
+    ) {
+        let chart = generated(seed, layout, density);
+        let count = chart.notes.len() as u32;
+        let inputs = perfect_inputs(&chart, audio_offset);
+        let mut session =
+            CatchSession::new(chart, HitWindows::default(), audio_offset, Progress::default());
+        session.run(&inputs);
+
+        prop_assert_eq!(session.stats().greats, count);
+        prop_assert_eq!(session.stats().max_combo, count);
+        prop_assert_ne!(session.outcome(), Some(Outcome::Lost));
+    }
+
+    #[test]
+    fn slightly_off_presses_still_hit(
+        seed in any::<u64>(),
+        error in -0.17f32..0.17,
+    ) {
+        //sparse enough that neighbouring notes of a lane never compete for a press
+        let chart = generated(seed, LaneLayout::Four, 0.5);
+        let inputs = perfect_inputs(&chart, error);
+        let mut session = session(chart);
+        session.run(&inputs);
+        prop_assert_eq!(session.stats().misses, 0);
+    }
+
+    #[test]
+    fn snapping_is_idempotent(time in -10.0f32..100.0, bpm in 40.0f32..240.0, division in 1u32..9) {
+        let chart = Chart { bpm, ..Default::default() };
+        let snapped = chart.snap(time, division);
+        prop_assert!((chart.snap(snapped, division) - snapped).abs() < 1e-3);
+        prop_assert!((snapped - time).abs() <= chart.beat_length() / division as f32 / 2.0 + 1e-3);
+    }
+
+    #[test]
+    fn toggling_twice_restores_the_chart(
+        seed in any::<u64>(),
+        time in 0.0f32..20.0,
+        lane in any_lane(),
+    ) {
+        let original = generated(seed, LaneLayout::Six, 1.0);
+        let mut chart = original.clone();
+        if chart.toggle_note(time, lane, 0.01) {
+            prop_assert!(!chart.toggle_note(time, lane, 0.01));
+            prop_assert_eq!(chart, original);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use fishing_hero::{
+    rhythm::Grade,
+    save::{self, SaveData, SaveError, SAVE_VERSION},
+    shop::STARTING_MONEY,
+};
+
+fn fixture(version: u32) -> String {
+    let path = format!(
+        "{}/tests/fixtures/saves/v{version}.ron",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("missing fixture {path}: {e}"))
+}
+
+#[test]
+fn every_past_version_loads() {
+    for version in 1..=SAVE_VERSION {
+        let data = save::load_from_str(&fixture(version))
+            .unwrap_or_else(|e| panic!("v{version} failed to load: {e}"));
+        assert_eq!(data.version, SAVE_VERSION);
+    }
+}
+
+#[test]
+fn v1_keeps_player_position() {
+    let data = save::load_from_str(&fixture(1)).unwrap();
+    assert_eq!(data.player.position, (4.5, -2.0));
+}
+
+#[test]
+fn v1_upgrades_with_an_empty_fish_log() {
+    let data = save::load_from_str(&fixture(1)).unwrap();
+    assert!(data.fish_log.records.is_empty());
+}
+
+#[test]
+fn v2_keeps_fish_log() {
+    let data = save::load_from_str(&fixture(2)).unwrap();
+    assert_eq!(data.player.position, (-3.0, 7.25));
+
+    let bass = data.fish_log.get("bass").unwrap();
+    assert_eq!(bass.count, 3);
+    assert_eq!(bass.largest_size, 41.5);
+    assert_eq!(bass.best_grade, Some(Grade::A));
+    assert_eq!(bass.first_caught, 1760745600);
+    assert_eq!(data.fish_log.get("minnow").unwrap().best_grade, None);
+}
+
+#[test]
+fn v2_upgrades_with_starting_money() {
+    let data = save::load_from_str(&fixture(2)).unwrap();
+    assert_eq!(data.inventory.money, STARTING_MONEY);
+    assert!(data.inventory.items.is_empty());
+    assert!(data.inventory.fish.is_empty());
+}
+
+#[test]
+fn v3_keeps_inventory() {
+    let data = save::load_from_str(&fixture(3)).unwrap();
+    assert_eq!(data.inventory.money, 312);
+    assert_eq!(data.inventory.count("worms"), 14);
+    assert_eq!(data.inventory.count("bamboo_rod"), 1);
+    assert_eq!(data.inventory.fish.len(), 1);
+    assert_eq!(data.inventory.fish[0].grade, Some(Grade::B));
+    assert_eq!(data.fish_log.get("trout").unwrap().count, 2);
+}
+
+#[test]
+fn round_trip_is_lossless() {
+    let mut data = SaveData::default();
+    data.player.position = (1.25, 3.0);
+    data.fish_log.record("perch", 22.0, Some(Grade::S), 1760918400);
+    data.inventory.money = 7;
+    data.inventory.items.insert("treble_hook".to_string(), 1);
+    let text = save::save_to_string(&data).unwrap();
+    assert_eq!(save::load_from_str(&text).unwrap(), data);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let text = format!("(version: {}, player: (position: (0.0, 0.0)))", SAVE_VERSION + 1);
+    assert!(matches!(
+        save::load_from_str(&text),
+        Err(SaveError::TooNew(v)) if v == SAVE_VERSION + 1
+    ));
+}
+
+#[test]
+fn version_zero_is_rejected() {
+    assert!(matches!(
+        save::load_from_str("(version: 0)"),
+        Err(SaveError::UnknownVersion(0))
+    ));
+}
+
+#[test]
+fn garbage_is_a_parse_error() {
+    assert!(matches!(
+        save::load_from_str("not a save"),
+        Err(SaveError::Parse(_))
+    ));
+}
@@ -0,0 +1,173 @@
+//! Buying, selling and fish prices, no engine needed.
+use fishing_hero::{
+    fish::{FishRegistry, FishSpecies},
+    rhythm::Grade,
+    shop::{fish_price, CaughtFish, Inventory, ItemKind, ShopCatalogue, ShopError, ShopItem},
+};
+
+fn item(id: &str, kind: ItemKind, price: u32) -> ShopItem {
+    ShopItem {
+        id: id.to_string(),
+        name: id.to_string(),
+        kind,
+        price,
+    }
+}
+
+fn catalogue() -> ShopCatalogue {
+    ShopCatalogue {
+        items: vec![
+            item("rod", ItemKind::Rod, 40),
+            item("worms", ItemKind::Bait, 2),
+            item("golden_rod", ItemKind::Rod, 1000),
+        ],
+    }
+}
+
+fn trout() -> FishSpecies {
+    FishSpecies {
+        id: "trout".to_string(),
+        name: "Trout".to_string(),
+        sprite: String::new(),
+        silhouette: String::new(),
+        rarity_weight: 1.0,
+        base_price: 20,
+        min_size: 20.0,
+        max_size: 60.0,
+        difficulty: 1.0,
+        chart: None,
+    }
+}
+
+fn registry() -> FishRegistry {
+    FishRegistry {
+        species: vec![trout()],
+    }
+}
+
+fn fish(species: &str, size: f32, grade: Option<Grade>) -> CaughtFish {
+    CaughtFish {
+        species: species.to_string(),
+        size,
+        grade,
+    }
+}
+
+#[test]
+fn buying_takes_the_money() {
+    let mut inventory = Inventory::default();
+    let money = inventory.money;
+    inventory.buy(&catalogue(), "rod").unwrap();
+    assert_eq!(inventory.money, money - 40);
+    assert_eq!(inventory.count("rod"), 1);
+}
+
+#[test]
+fn rods_are_bought_once() {
+    let mut inventory = Inventory {
+        money: 500,
+        ..Default::default()
+    };
+    inventory.buy(&catalogue(), "rod").unwrap();
+    assert_eq!(
+        inventory.buy(&catalogue(), "rod"),
+        Err(ShopError::AlreadyOwned)
+    );
+    assert_eq!(inventory.money, 460);
+    assert_eq!(inventory.count("rod"), 1);
+}
+
+#[test]
+fn bait_stacks() {
+    let mut inventory = Inventory::default();
+    inventory.buy(&catalogue(), "worms").unwrap();
+    inventory.buy(&catalogue(), "worms").unwrap();
+    assert_eq!(inventory.count("worms"), 2);
+}
+
+#[test]
+fn cannot_buy_without_money() {
+    let mut inventory = Inventory::default();
+    let before = inventory.clone();
+    assert_eq!(
+        inventory.buy(&catalogue(), "golden_rod"),
+        Err(ShopError::NotEnoughMoney)
+    );
+    assert_eq!(inventory, before);
+}
+
+#[test]
+fn unknown_items_are_not_for_sale() {
+    let mut inventory = Inventory::default();
+    assert_eq!(
+        inventory.buy(&catalogue(), "anchor"),
+        Err(ShopError::UnknownItem)
+    );
+}
+
+#[test]
+fn selling_pays_the_fish_price() {
+    let mut inventory = Inventory {
+        money: 0,
+        fish: vec![
+            fish("trout", 40.0, Some(Grade::A)),
+            fish("trout", 20.0, None),
+        ],
+        ..Default::default()
+    };
+    let price = fish_price(&trout(), 40.0, Some(Grade::A));
+    assert_eq!(inventory.sell_fish(&registry(), 0), Some(price));
+    assert_eq!(inventory.money, price);
+    assert_eq!(inventory.fish, vec![fish("trout", 20.0, None)]);
+}
+
+#[test]
+fn selling_past_the_bag_does_nothing() {
+    let mut inventory = Inventory::default();
+    let before = inventory.clone();
+    assert_eq!(inventory.sell_fish(&registry(), 0), None);
+    assert_eq!(inventory, before);
+}
+
+#[test]
+fn unknown_species_are_not_sold() {
+    let mut inventory = Inventory {
+        fish: vec![fish("kraken", 300.0, Some(Grade::S))],
+        ..Default::default()
+    };
+    let before = inventory.clone();
+    assert_eq!(inventory.sell_fish(&registry(), 0), None);
+    assert_eq!(inventory, before);
+}
+
+#[test]
+fn bigger_fish_are_worth_more() {
+    let species = trout();
+    assert_eq!(fish_price(&species, 20.0, None), 15);
+    assert_eq!(fish_price(&species, 40.0, None), 23);
+    assert_eq!(fish_price(&species, 60.0, None), 30);
+    //sizes outside the species' range are clamped
+    assert_eq!(fish_price(&species, 5.0, None), 15);
+    assert_eq!(fish_price(&species, 90.0, None), 30);
+}
+
+#[test]
+fn cleaner_catches_are_worth_more() {
+    let species = trout();
+    let prices: Vec<u32> = [Grade::D, Grade::C, Grade::B, Grade::A, Grade::S]
+        .into_iter()
+        .map(|grade| fish_price(&species, 60.0, Some(grade)))
+        .collect();
+    assert_eq!(prices, vec![23, 27, 30, 38, 45]);
+    assert_eq!(fish_price(&species, 60.0, None), 30);
+}
+
+#[test]
+fn species_without_a_size_range_ignore_size() {
+    let species = FishSpecies {
+        min_size: 30.0,
+        max_size: 30.0,
+        ..trout()
+    };
+    assert_eq!(fish_price(&species, 30.0, None), 20);
+}